@@ -1,5 +1,4 @@
-use std::path::Path;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, BTreeSet};
 use colored::Colorize;
 
 use crate::util::*;
@@ -11,9 +10,7 @@ fn block_compare<'a>(bind_db: &BindDB, pair: &ExecPair, in_blk: &'a Block, mut o
 	if let Some(sym_name) = pair.input.fns.get(&in_blk.address.function_addr).and_then(|x| x.name.as_ref()) {
 		let matching = bind_db.binds.get(sym_name).and_then(|x| x.get_addr());
 		if let Some(matching) = matching {
-			if out_blks.iter().find(|x| x.address.function_addr == matching).is_none() {
-				return None;
-			}
+			out_blks.iter().find(|x| x.address.function_addr == matching)?;
 
 			let possible: Vec<_> = out_blks.into_iter().filter(|x| x.address.function_addr == matching).collect();
 			if possible.len() == 1 {
@@ -24,22 +21,47 @@ fn block_compare<'a>(bind_db: &BindDB, pair: &ExecPair, in_blk: &'a Block, mut o
 		}
 	}
 
+	// import check! library calls keep their names between versions
+	let imports = |x: &Block| x.calls.iter()
+		.filter_map(|x| match x {
+			Dest::Import(x) => Some(x.clone()),
+			_ => None
+		})
+		.collect::<Vec<_>>();
+	let in_imports = imports(in_blk);
+	if !in_imports.is_empty() {
+		let imports_matching: Vec<_> = out_blks.iter().filter(|x| imports(x) == in_imports).collect();
+		if imports_matching.len() == 1 {
+			return Some(imports_matching[0]);
+		}
+	}
+
 	// string check!
 	let strings_matching: Vec<_> = out_blks.iter().filter(|x| x.strings == in_blk.strings).collect();
 	if strings_matching.len() == 1 {
 		return Some(strings_matching[0]);
 	}
 
+	// constant check! immediates survive a change of instruction set, though not their order
+	if !in_blk.constants.is_empty() {
+		let in_constants: HashSet<_> = in_blk.constants.iter().collect();
+		let constants_matching: Vec<_> = out_blks.iter().filter(|x| x.constants.iter().collect::<HashSet<_>>() == in_constants).collect();
+		if constants_matching.len() == 1 {
+			return Some(constants_matching[0]);
+		}
+	}
+
 	// call check!
 	let calls_matching: Vec<_> = out_blks.iter()
 		.filter(|x| x.calls.len() == in_blk.calls.len())
 		.filter(|x| x.calls.iter().zip(&in_blk.calls).all(|(o, i)|
 			match (i, o) {
 				(Dest::Unknown, Dest::Unknown) => true,
+				(Dest::Virtual(a, x), Dest::Virtual(b, y)) => x == y && (a.is_none() || b.is_none() || a == b),
+				(Dest::Import(x), Dest::Import(y)) => x == y,
 				(Dest::Known(i), Dest::Known(o)) => {
-					pair.input.fns.get(&i)
-						.map(|x| x.name.clone())
-						.flatten()
+					pair.input.fns.get(i)
+						.and_then(|x| x.name.clone())
 						.and_then(|x| bind_db.binds.get(&x))
 						.map(|x| x.get_addr().map(|x| x == *o).unwrap_or(false))
 						.unwrap_or(true)
@@ -61,8 +83,205 @@ fn block_compare<'a>(bind_db: &BindDB, pair: &ExecPair, in_blk: &'a Block, mut o
 	None
 }
 
-fn block_traverse<'a>(binds: &BindDB, pair: &'a ExecPair, blocks: Vec<(&'a Block, &'a Block)>) -> Vec<(&'a Block, &'a Block)> {
+fn block_at(db: &ExecDB, function_addr: u64, addr: u64) -> Option<&Block> {
+	db.addr_to_block(&Address {
+		function_addr,
+		block_addr: addr,
+		addr
+	})
+}
+
+fn successor_pair<'a>(pair: &'a ExecPair, in_blk: &Block, out_blk: &Block, x: u64, y: u64) -> Option<(&'a Block, &'a Block)> {
+	(
+		block_at(&pair.input, in_blk.address.function_addr, x)?,
+		block_at(&pair.output, out_blk.address.function_addr, y)?
+	).as_some()
+}
+
+fn successors<'a>(db: &'a ExecDB, blk: &Block) -> Vec<&'a Block> {
+	let dests = match &blk.branch {
+		Branch::Return | Branch::TailCall(_) => vec![],
+		Branch::Neutral(x) => vec![x],
+		Branch::Equality(x, y) | Branch::Inequality(x, y) => vec![x, y],
+		Branch::Switch(x) => x.iter().collect()
+	};
+
+	dests.into_iter()
+		.filter_map(|x| match x {
+			Dest::Known(x) => block_at(db, blk.address.function_addr, *x),
+			_ => None
+		})
+		.collect()
+}
+
+// How many blocks past a mismatch we look for the matching successor
+const LOOKAHEAD: usize = 2;
+
+/// Blocks reachable from `blk` within `depth` branches, not including itself
+fn nearby<'a>(db: &'a ExecDB, blk: &'a Block, depth: usize) -> Vec<&'a Block> {
+	let mut found: Vec<&Block> = Vec::new();
+	let mut current = vec![blk];
+
+	for _ in 0..depth {
+		let mut next = Vec::new();
+		for x in current.into_iter().flat_map(|x| successors(db, x)) {
+			if x != blk && !found.contains(&x) && !next.contains(&x) {
+				next.push(x);
+			}
+		}
+		found.extend(&next);
+		current = next;
+	}
+
+	found
+}
+
+/// Weighted longest common subsequence, returns the index pairs of the best alignment in order
+pub fn align<A, B>(a: &[A], b: &[B], score: impl Fn(&A, &B) -> u32) -> Vec<(usize, usize)> {
+	let mut table = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+
+	for i in (0..a.len()).rev() {
+		for j in (0..b.len()).rev() {
+			let here = score(&a[i], &b[j]);
+			let matched = if here > 0 { here + table[i + 1][j + 1] } else { 0 };
+			table[i][j] = matched.max(table[i + 1][j]).max(table[i][j + 1]);
+		}
+	}
+
+	let mut out = Vec::new();
+	let (mut i, mut j) = (0, 0);
+	while i < a.len() && j < b.len() {
+		let here = score(&a[i], &b[j]);
+		if here > 0 && table[i][j] == here + table[i + 1][j + 1] {
+			out.push((i, j));
+			i += 1;
+			j += 1;
+		} else if table[i + 1][j] >= table[i][j + 1] {
+			i += 1;
+		} else {
+			j += 1;
+		}
+	}
+
+	out
+}
+
+/// Output address to the symbol bound there
+pub fn bound_at(binds: &BindDB) -> HashMap<u64, &String> {
+	binds.binds.iter()
+		.filter_map(|(x, y)| (y.get_addr()?, x).as_some())
+		.collect()
+}
+
+/// How strongly two call destinations look like the same callee, 0 if they can't be
+pub fn call_score(binds: &BindDB, pair: &ExecPair, bound: &HashMap<u64, &String>, i: &Dest, o: &Dest) -> u32 {
+	match (i, o) {
+		(Dest::Unknown, Dest::Unknown) => 1,
+		// Library calls keep their names between versions, which makes them the best anchors
+		(Dest::Import(x), Dest::Import(y)) if x == y => 3,
+		(Dest::Virtual(Some(a), x), Dest::Virtual(Some(b), y)) if x == y && a == b => 2,
+		(Dest::Virtual(None, x), Dest::Virtual(_, y)) | (Dest::Virtual(_, x), Dest::Virtual(None, y)) if x == y => 1,
+		(Dest::Known(i), Dest::Known(o)) => {
+			let Some(name) = pair.input.fns.get(i).and_then(|x| x.name.as_ref()) else {
+				return 1;
+			};
+
+			if bound.get(o).is_some_and(|x| *x != name) {
+				return 0;
+			}
+
+			match binds.binds.get(name) {
+				Some(Bind::Verified(x)) | Some(Bind::Unverified(x)) if x == o => 3,
+				Some(Bind::Not(x)) if !x.contains(o) => 1,
+				None => 1,
+				// Bound elsewhere, rejected here, or inlined and so gone from the output
+				_ => 0
+			}
+		},
+		_ => 0
+	}
+}
+
+fn block_similarity(binds: &BindDB, pair: &ExecPair, bound: &HashMap<u64, &String>, a: &Block, b: &Block) -> u32 {
+	let strings = a.strings.iter().filter(|x| b.strings.contains(x)).count() as u32;
+	let constants = a.constants.iter().filter(|x| b.constants.contains(x)).count() as u32;
+	let calls: u32 = align(&a.calls, &b.calls, |x, y| call_score(binds, pair, bound, x, y)).into_iter()
+		.map(|(x, y)| call_score(binds, pair, bound, &a.calls[x], &b.calls[y]))
+		.sum();
+
+	// Empty blocks look alike everywhere, so only content counts as evidence
+	let content = strings * 2 + constants + calls;
+	if content == 0 {
+		0
+	} else {
+		content + (std::mem::discriminant(&a.branch) == std::mem::discriminant(&b.branch)) as u32
+	}
+}
+
+/// The single best scoring candidate, if there is one
+fn best_match<'a>(candidates: &[&'a Block], score: impl Fn(&Block) -> u32) -> Option<&'a Block> {
+	let scored: Vec<_> = candidates.iter().map(|x| (score(x), *x)).filter(|x| x.0 > 0).collect();
+	let best = scored.iter().map(|x| x.0).max()?;
+
+	match scored.iter().filter(|x| x.0 == best).collect::<Vec<_>>()[..] {
+		[x] => Some(x.1),
+		_ => None
+	}
+}
+
+/// Pairs the successors of two blocks whose branches don't line up, looking past inserted or removed blocks
+fn realign<'a>(binds: &BindDB, pair: &'a ExecPair, bound: &HashMap<u64, &String>, in_blk: &'a Block, out_blk: &'a Block) -> Vec<(&'a Block, &'a Block)> {
+	let in_near = nearby(&pair.input, in_blk, LOOKAHEAD);
+	let out_near = nearby(&pair.output, out_blk, LOOKAHEAD);
+
+	let forward = successors(&pair.input, in_blk).into_iter()
+		.filter_map(|x| (x, best_match(&out_near, |y| block_similarity(binds, pair, bound, x, y))?).as_some());
+	let backward = successors(&pair.output, out_blk).into_iter()
+		.filter_map(|y| (best_match(&in_near, |x| block_similarity(binds, pair, bound, x, y))?, y).as_some());
+
+	let mut out: Vec<_> = forward.chain(backward).collect();
+	out.dedup();
+	out
+}
+
+/// Pairs the successors of two conditional branches, swapping them when the condition looks inverted
+///
+/// `je`/`jne` or `jl`/`jge` with the arms swapped is the same branch, so both pairings are scored
+/// on how alike the successor blocks are. Ties keep the straight pairing when both branches are
+/// the same kind, otherwise there is nothing to go on and None is returned.
+fn conditional_pairs<'a>(
+	binds: &BindDB,
+	pair: &'a ExecPair,
+	bound: &HashMap<u64, &String>,
+	in_blk: &Block,
+	out_blk: &Block,
+	(x1, x2): (u64, u64),
+	(y1, y2): (u64, u64)
+) -> Option<Vec<(&'a Block, &'a Block)>> {
+	let same_kind = std::mem::discriminant(&in_blk.branch) == std::mem::discriminant(&out_blk.branch);
+
+	let straight: Vec<_> = [(x1, y1), (x2, y2)].into_iter()
+		.filter_map(|(x, y)| successor_pair(pair, in_blk, out_blk, x, y))
+		.collect();
+	let swapped: Vec<_> = [(x1, y2), (x2, y1)].into_iter()
+		.filter_map(|(x, y)| successor_pair(pair, in_blk, out_blk, x, y))
+		.collect();
+
+	let score = |pairs: &[(&Block, &Block)]| pairs.iter().map(|(x, y)| block_similarity(binds, pair, bound, x, y)).sum::<u32>();
+	let (straight_score, swapped_score) = (score(&straight), score(&swapped));
+
+	if swapped_score > straight_score {
+		Some(swapped)
+	} else if straight_score > swapped_score || same_kind {
+		Some(straight)
+	} else {
+		None
+	}
+}
+
+pub fn block_traverse<'a>(binds: &BindDB, pair: &'a ExecPair, blocks: Vec<(&'a Block, &'a Block)>) -> Vec<(&'a Block, &'a Block)> {
 	let mut total_blocks: Vec<(&'a Block, &'a Block)> = Vec::new();
+	let bound = bound_at(binds);
 
 	let mut current_blocks = blocks.clone();
 
@@ -73,7 +292,7 @@ fn block_traverse<'a>(binds: &BindDB, pair: &'a ExecPair, blocks: Vec<(&'a Block
 		for (in_blk, out_blk) in &current_blocks {
 			let has_inline = in_blk.calls.iter().filter_map(|x| match x {
 				Dest::Known(x) => pair.input.fns.get(x)?.name.clone(),
-				Dest::Unknown => None
+				_ => None
 			}).any(|x| matches!(binds.binds.get(&x), Some(Bind::Inline)));
 
 			if has_inline {
@@ -81,93 +300,30 @@ fn block_traverse<'a>(binds: &BindDB, pair: &'a ExecPair, blocks: Vec<(&'a Block
 			}
 
 			match (&in_blk.branch, &out_blk.branch) {
+				(Branch::Return, Branch::Return) | (Branch::TailCall(_), Branch::TailCall(_)) => (),
 				(Branch::Neutral(Dest::Known(x)), Branch::Neutral(Dest::Known(y))) => {
-					(
-						pair.input.addr_to_block(&Address {
-							function_addr: in_blk.address.function_addr,
-							block_addr: *x,
-							addr: *x
-						}),
-						pair.output.addr_to_block(&Address {
-							function_addr: out_blk.address.function_addr,
-							block_addr: *y,
-							addr: *y
-						})
-					).as_some()
-					.and_then(|(x, y)| (x?,y?).as_some())
-					.map(|x| next_blocks.push(x));
+					next_blocks.extend(successor_pair(pair, in_blk, out_blk, *x, *y));
 				},
-				(Branch::Equality(Dest::Known(xeq), Dest::Known(xneq)), Branch::Equality(Dest::Known(yeq), Dest::Known(yneq))) => {
-					//next_blocks.push((xeq, yeq));
-					(
-						pair.input.addr_to_block(&Address {
-							function_addr: in_blk.address.function_addr,
-							block_addr: *xeq,
-							addr: *xeq
-						}), 
-						pair.output.addr_to_block(&Address {
-							function_addr: out_blk.address.function_addr,
-							block_addr: *yeq,
-							addr: *yeq
-						})
-					).as_some()
-					 .and_then(|(x, y)| (x?,y?).as_some())
-					 .map(|x| next_blocks.push(x));
-
-					//next_blocks.push((xneq, yneq));
-					(
-						pair.input.addr_to_block(&Address {
-							function_addr: in_blk.address.function_addr,
-							block_addr: *xneq,
-							addr: *xneq
-						}), 
-						pair.output.addr_to_block(&Address {
-							function_addr: out_blk.address.function_addr,
-							block_addr: *yneq,
-							addr: *yneq
-						})
-					).as_some()
-					 .and_then(|(x, y)| (x?,y?).as_some())
-					 .map(|x| next_blocks.push(x));
-				
+				(
+					Branch::Equality(Dest::Known(x1), Dest::Known(x2)) | Branch::Inequality(Dest::Known(x1), Dest::Known(x2)),
+					Branch::Equality(Dest::Known(y1), Dest::Known(y2)) | Branch::Inequality(Dest::Known(y1), Dest::Known(y2))
+				) => {
+					match conditional_pairs(binds, pair, &bound, in_blk, out_blk, (*x1, *x2), (*y1, *y2)) {
+						Some(x) => next_blocks.extend(x),
+						None => next_blocks.extend(realign(binds, pair, &bound, in_blk, out_blk))
+					}
 				},
-				(Branch::Inequality(Dest::Known(xgt), Dest::Known(xlt)), Branch::Inequality(Dest::Known(ygt), Dest::Known(ylt))) => {
-					//next_blocks.push((xgt, ygt));
-					(
-						pair.input.addr_to_block(&Address {
-							function_addr: in_blk.address.function_addr,
-							block_addr: *xgt,
-							addr: *xgt
-						}), 
-						pair.output.addr_to_block(&Address {
-							function_addr: out_blk.address.function_addr,
-							block_addr: *ygt,
-							addr: *ygt
-						})
-					).as_some()
-					 .and_then(|(x, y)| (x?,y?).as_some())
-					 .map(|x| next_blocks.push(x));
-
-
-					//next_blocks.push((xlt, ylt));
-					(
-						pair.input.addr_to_block(&Address {
-							function_addr: in_blk.address.function_addr,
-							block_addr: *xlt,
-							addr: *xlt
-						}), 
-						pair.output.addr_to_block(&Address {
-							function_addr: out_blk.address.function_addr,
-							block_addr: *ylt,
-							addr: *ylt
-						})
-					).as_some()
-					 .and_then(|(x, y)| (x?,y?).as_some())
-					 .map(|x| next_blocks.push(x));
-				
+
+				// Cases are in value order, so equal tables line up case by case
+				(Branch::Switch(x), Branch::Switch(y)) if x.len() == y.len() => {
+					next_blocks.extend(x.iter().zip(y).filter_map(|x| match x {
+						(Dest::Known(x), Dest::Known(y)) => successor_pair(pair, in_blk, out_blk, *x, *y),
+						_ => None
+					}));
 				},
 
-				_ => continue
+				// Something was added or removed here, find where the two sides meet again
+				_ => next_blocks.extend(realign(binds, pair, &bound, in_blk, out_blk))
 			}
 		}
 
@@ -196,16 +352,16 @@ pub fn xref_binds(bind_db: &BindDB, pair: &ExecPair, xrefs: Vec<(&Vec<Address>,
 	// Multiple xrefs
 	xrefs.iter()
 		.filter(|(x, y)| x.len() > 1 && y.len() > 1)
-		.map(|(x, y)| {
-			let oblocks: Vec<_> = y.iter().map(|x| pair.output.addr_to_block(x).unwrap()).collect();
+		.flat_map(|(x, y)| {
+			let oblocks: Vec<_> = y.iter().filter_map(|x| pair.output.addr_to_block(x)).collect();
 			x.iter()
-				.map(move |x| (pair.input.addr_to_block(x).unwrap(), oblocks.clone()))
-				.map(|(x, y)| (x, block_compare(bind_db, pair, x, y)?).as_some())
-		}).flatten()
-		.filter_map(|x| x.and_then(|(x, y)| (
-			pair.input.fns.get(&x.address.function_addr).unwrap().name.clone()?,
+				.filter_map(|x| pair.input.addr_to_block(x))
+				.filter_map(move |x| (x, block_compare(bind_db, pair, x, oblocks.clone())?).as_some())
+		})
+		.filter_map(|(x, y)| (
+			pair.input.fns.get(&x.address.function_addr)?.name.clone()?,
 			y
-		).as_some()))
+		).as_some())
 		.for_each(|(x, y)| {
 			output.insert(x, y.address.function_addr);
 		});
@@ -214,33 +370,38 @@ pub fn xref_binds(bind_db: &BindDB, pair: &ExecPair, xrefs: Vec<(&Vec<Address>,
 }
 
 pub fn block_binds(bind_db: &BindDB, pair: &ExecPair, blocks: Vec<(&Block, &Block)>) -> HashMap<String, u64> {
-	blocks.into_iter().map(|(i_block, o_block)| i_block.calls.iter().zip(&o_block.calls).map(|(x, y)| {
-		match (x, y) {
-			(Dest::Unknown, Dest::Unknown) => Ok(None),
-			(Dest::Known(i), Dest::Known(o)) => {
-				let out = pair.input.fns.get(&i)
-					.and_then(|x| x.name.as_ref())
-					.map(|x| (x.clone(), *o));
-
-				if let Some(ref x) = out {
-					if matches!(bind_db.binds.get(&x.0), Some(Bind::Inline)) {
-						// stop immediately for inlines
-						return Err(());
-					}
-				}
+	let bound = bound_at(bind_db);
+	let mut output = HashMap::new();
+
+	for (i_block, o_block) in blocks {
+		// Align rather than zip, so one added or inlined call doesn't throw off the rest
+		let aligned = align(&i_block.calls, &o_block.calls, |x, y| call_score(bind_db, pair, &bound, x, y));
 
-				Ok(out)
-			},
-			_ => {
-				println!("Block mismatch! {} - {} (Potential Inline?)",
-					i_block.address.block_addr.as_hex().blue(), 
-					o_block.address.block_addr.as_hex().blue()
-				);
+		if aligned.len() < i_block.calls.len().max(o_block.calls.len()) {
+			println!("Block mismatch! {} - {} (Potential Inline?)",
+				i_block.address.block_addr.as_addr(pair.input.meta.base).blue(),
+				o_block.address.block_addr.as_addr(pair.output.meta.base).blue()
+			);
+		}
+
+		aligned.into_iter()
+			.filter_map(|(x, y)| match (&i_block.calls[x], &o_block.calls[y]) {
+				(Dest::Known(i), Dest::Known(o)) => (pair.input.fns.get(i)?.name.clone()?, *o).as_some(),
+				_ => None
+			})
+			.for_each(|(x, y)| {
+				output.insert(x, y);
+			});
 
-				Err(())
+		// A tail call is a call that happens to end the block
+		if let (Branch::TailCall(x @ Dest::Known(i)), Branch::TailCall(y @ Dest::Known(o))) = (&i_block.branch, &o_block.branch) {
+			if let Some(name) = pair.input.fns.get(i).and_then(|x| x.name.clone()).filter(|_| call_score(bind_db, pair, &bound, x, y) > 0) {
+				output.insert(name, *o);
 			}
 		}
-	}).take_while(|x| x.is_ok()).filter_map(|x| x.unwrap())).flatten().collect()
+	}
+
+	output
 }
 
 // Strategies
@@ -259,7 +420,7 @@ pub fn call_block_strat(pair: &ExecPair, binds: &BindDB) -> HashMap<String, u64>
 			i,
 			o.iter().filter_map(|x| pair.output.addr_to_block(x)).collect::<Vec<_>>()
 		))
-		.map(|(i, o)|
+		.flat_map(|(i, o)|
 			i.iter()
 				.filter_map(|x| (x, pair.input.fns.get(&x.function_addr)?.name.as_ref()?).as_some())
 				.filter_map(|(x, y)| (
@@ -280,7 +441,7 @@ pub fn call_block_strat(pair: &ExecPair, binds: &BindDB) -> HashMap<String, u64>
 						}
 					}
 				).as_some()).collect::<Vec<_>>()
-		).flatten().collect();
+		).collect();
 
 	block_binds(binds, pair, blocks)
 }
@@ -324,8 +485,126 @@ pub fn string_xref_strat(pair: &ExecPair, binds: &BindDB) -> HashMap<String, u64
 	xref_binds(binds, pair, string_pairs)
 }
 
+pub fn import_xref_strat(pair: &ExecPair, binds: &BindDB) -> HashMap<String, u64> {
+	let import_pairs: Vec<(&Vec<Address>, &Vec<Address>)> = pair.input.imports.iter()
+		.filter_map(|x| (&x.1.xrefs, &pair.output.imports.get(x.0)?.xrefs).as_some())
+		.collect();
+
+	xref_binds(binds, pair, import_pairs)
+}
+
+/// Strings used by the blocks referencing a global, which tend to survive when its name doesn't
+fn global_strings<'a>(db: &'a ExecDB, global: &Global) -> BTreeSet<&'a String> {
+	global.xrefs.iter()
+		.filter_map(|x| db.addr_to_block(x))
+		.flat_map(|x| &x.strings)
+		.collect()
+}
+
+fn global_signatures(db: &ExecDB) -> HashMap<BTreeSet<&String>, Vec<u64>> {
+	let mut out: HashMap<BTreeSet<&String>, Vec<u64>> = HashMap::new();
+	db.globals.values()
+		.map(|x| (global_strings(db, x), x.address))
+		.filter(|x| !x.0.is_empty())
+		.for_each(|(x, y)| out.entry(x).or_default().push(y));
+	out
+}
+
+/// Pairs up globals that are the same object in both binaries, by symbol name, vtable or the strings around them
+pub fn match_globals(pair: &ExecPair) -> Vec<(&Global, &Global)> {
+	let (in_ptr, out_ptr) = (pair.input.meta.ptr_size(), pair.output.meta.ptr_size());
+	let mut matched: HashMap<u64, &Global> = HashMap::new();
+
+	let out_names: HashMap<&String, &Global> = pair.output.globals.values()
+		.filter_map(|x| (x.name.as_ref()?, x).as_some())
+		.collect();
+
+	// Globals are either the vtable's address point or its start, pointers may differ in size
+	let in_vtables: HashMap<u64, (&String, bool)> = pair.input.vtables.values()
+		.flat_map(|x| [(x.address, (&x.name, false)), (x.start(in_ptr), (&x.name, true))])
+		.collect();
+
+	for global in pair.input.globals.values() {
+		let by_name = global.name.as_ref().and_then(|x| out_names.get(x)).copied();
+		let by_vtable = in_vtables.get(&global.address)
+			.and_then(|(x, start)| pair.output.vtables.get(*x).map(|x| if *start { x.start(out_ptr) } else { x.address }))
+			.and_then(|x| pair.output.globals.get(&x));
+
+		if let Some(x) = by_name.or(by_vtable) {
+			matched.insert(global.address, x);
+		}
+	}
+
+	// Only signatures that are unique on both sides say anything
+	let out_signatures = global_signatures(&pair.output);
+
+	for (signature, addrs) in global_signatures(&pair.input) {
+		if let ([x], Some([y])) = (&addrs[..], out_signatures.get(&signature).map(|x| &x[..])) {
+			matched.entry(*x).or_insert(&pair.output.globals[y]);
+		}
+	}
+
+	matched.into_iter()
+		.map(|(x, y)| (&pair.input.globals[&x], y))
+		.collect()
+}
+
+pub fn global_xref_strat(pair: &ExecPair, binds: &BindDB) -> HashMap<String, u64> {
+	let global_pairs: Vec<(&Vec<Address>, &Vec<Address>)> = match_globals(pair).into_iter()
+		.map(|(x, y)| (&x.xrefs, &y.xrefs))
+		.collect();
+
+	xref_binds(binds, pair, global_pairs)
+}
+
+pub type Strategy = fn(&ExecPair, &BindDB) -> HashMap<String, u64>;
+
+// Default order for `run`, each strategy sees the binds found by the ones before it
+pub const STRATEGIES: &[(&str, Strategy)] = &[
+	("string_xref", string_xref_strat),
+	("import_xref", import_xref_strat),
+	("global_xref", global_xref_strat),
+	("block_traverse", block_traverse_strat),
+	("call_xref", call_xref_strat),
+	("call_block", call_block_strat)
+];
+
+// How much an unverified bind from each source is trusted, verified binds are always 1.0
+pub const CONFIDENCE: &[(&str, f64)] = &[
+	("vtable", 0.95),
+	("string_xref", 0.8),
+	("import_xref", 0.8),
+	("global_xref", 0.75),
+	("call_xref", 0.7),
+	("block_traverse", 0.6),
+	("call_block", 0.5)
+];
+
+pub fn confidence(bind: &Bind, source: Option<&str>) -> f64 {
+	match bind {
+		Bind::Unverified(_) => source
+			.and_then(|x| CONFIDENCE.iter().find(|y| y.0 == x))
+			.map(|x| x.1)
+			.unwrap_or(0.5),
+		_ => 1.0
+	}
+}
+
+/// Runs the named strategies in order
+pub fn run_strategies(pair: &ExecPair, binds: &mut BindDB, names: &[String]) {
+	if pair.input.meta.arch() != pair.output.meta.arch() {
+		println!("Matching {} against {}, only calls, strings and constants carry over", pair.input.meta.arch.yellow(), pair.output.meta.arch.yellow());
+	}
+
+	for name in names {
+		if let Some((name, strat)) = STRATEGIES.iter().find(|x| x.0 == name).warn_if(format!("Unknown strategy: {}", name)) {
+			binds.process(pair, name, strat(pair, binds));
+		}
+	}
+}
+
 impl BindDB {
-	pub fn process(&mut self, new: HashMap<String, u64>, outfile: &Path) {
+	pub fn process(&mut self, pair: &ExecPair, source: &str, new: HashMap<String, u64>) {
 		let before_count = self.binds.len();
 		let mut verify_count = 0;
 
@@ -334,51 +613,51 @@ impl BindDB {
 		for (k, v) in new {
 			if let Some(x) = self.binds.get(&k) {
 				if self.binds.iter().any(|x| *x.1 == Bind::Verified(v)) && !matches!(x, Bind::Verified(_)) {
-					self.binds.insert(k.clone(), Bind::Not(vec![v]));
+					self.set(&k, Bind::Not(vec![v]), source);
 				}
 			}
 
-			if let Some(x) = self.binds.get_mut(&k) {
-				match x {
-					Bind::Unverified(a) => {
-						if *a != v {
-							// CONFLICT
-							if let Some(confirm) = conflict_confirm(&k, v) {
+			match self.binds.get(&k).cloned() {
+				Some(Bind::Unverified(a)) => {
+					if a != v {
+						// CONFLICT
+						if let Some(confirm) = conflict_confirm(pair, self, &k, v) {
+							if confirm {
+								verify_count += 1;
+								self.set(&k, Bind::Verified(v), source);
+							} else if let Some(confirm) = conflict_confirm(pair, self, &k, a) {
 								if confirm {
 									verify_count += 1;
-									*x = Bind::Verified(v);	
-								} else if let Some(confirm) = conflict_confirm(&k, *a) {
-									if confirm {
-										verify_count += 1;
-										*x = Bind::Verified(*a);
-									} else {
-										*x = Bind::Not(vec![*a, v]);
-									}
+									self.set(&k, Bind::Verified(a), source);
 								} else {
-									*x = Bind::Not(vec![v]);
+									self.set(&k, Bind::Not(vec![a, v]), source);
 								}
+							} else {
+								self.set(&k, Bind::Not(vec![v]), source);
 							}
 						}
 					}
-					Bind::Not(a) => {
-						if a.iter().find(|x| **x == v).is_none() {
-							if let Some(confirm) = conflict_confirm(&k, v) {
-								if confirm {
-									verify_count += 1;
-									*x = Bind::Verified(v);
-								} else {
-									a.push(v);
-								}
+				}
+				Some(Bind::Not(mut a)) => {
+					if !a.contains(&v) {
+						if let Some(confirm) = conflict_confirm(pair, self, &k, v) {
+							if confirm {
+								verify_count += 1;
+								self.set(&k, Bind::Verified(v), source);
+							} else {
+								a.push(v);
+								self.set(&k, Bind::Not(a), source);
 							}
 						}
 					}
-					Bind::Verified(_) | Bind::Inline  => {}
 				}
-			} else {
-				self.binds.insert(k, Bind::Unverified(v));
+				Some(Bind::Verified(_)) | Some(Bind::Inline) => {}
+				None => {
+					self.set(&k, Bind::Unverified(v), source);
+				}
 			}
 
-			std::fs::write(outfile, serde_json::to_string_pretty(&self).unwrap()).unwrap();
+			self.checkpoint();
 		}
 
 		// mfw rust
@@ -392,35 +671,37 @@ impl BindDB {
 				if appearances.len() > 1 {
 					println!("Processing {} duplicates", appearances.len());
 					if let Some(verified) = appearances.iter().find(|x| matches!(self.binds.get(x.0), Some(Bind::Verified(_)))) {
-						self.binds.insert(verified.0.to_string(), verified.1.clone());
+						self.set(verified.0, verified.1.clone(), source);
 					} else {
 						for bind in &appearances {
-							if let Some(confirm) = conflict_confirm(bind.0, *a) {
+							if let Some(confirm) = conflict_confirm(pair, self, bind.0, *a) {
 								if confirm {
 									verify_count += 1;
-									self.binds.insert(bind.0.to_string(), Bind::Verified(*a));
+									self.set(bind.0, Bind::Verified(*a), source);
 
 									for bind in &appearances {
 										if let Some(Bind::Unverified(_)) = self.binds.get(bind.0) {
-											self.binds.remove(bind.0);
+											self.remove(bind.0, source);
 										}
 									}
 									break;
 								} else {
-									self.binds.insert(bind.0.to_string(), Bind::Not(vec![*a]));
+									self.set(bind.0, Bind::Not(vec![*a]), source);
 								}
 							} else {
-								self.binds.remove(bind.0);
+								self.remove(bind.0, source);
 								break;
 							}
 						}
 					}
 
-					std::fs::write(outfile, serde_json::to_string_pretty(&self).unwrap()).unwrap();
+					self.checkpoint();
 				}
 			}
 		}
 
+		self.save();
+
 		println!("Added {} symbols", (self.binds.len() - before_count).to_string().bright_green());
 
 		if verify_count > 0 {
@@ -429,9 +710,7 @@ impl BindDB {
 	}
 
 	pub fn new(pair: &ExecPair) -> Self {
-		let mut bind_db = BindDB {
-			binds: HashMap::new()
-		};
+		let mut bind_db = BindDB::default();
 
 		// Vtables
 		pair.input.vtables.values()
@@ -440,7 +719,7 @@ impl BindDB {
 			.flatten()
 			.filter_map(|(i, o)| (i?.name.clone()?, Bind::Verified(*o)).as_some())
 			.for_each(|(x, y)| {
-				bind_db.binds.insert(x, y);
+				bind_db.set(&x, y, "vtable");
 			});
 
 		// Do a little string xref
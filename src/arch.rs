@@ -0,0 +1,384 @@
+use std::collections::HashMap;
+
+use crate::db::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Arch {
+	X86,
+	Arm,
+	Arm64,
+	// Anything else gets every table at once
+	Unknown
+}
+
+/// What a block's last instruction does, independent of how the ISA spells it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Flow {
+	Return,
+	Jump,
+	Equal,
+	NotEqual,
+	Greater,
+	Less,
+	Fallthrough
+}
+
+impl Arch {
+	/// From rizin's `arch` and `bits`
+	pub fn new(name: &str, bits: u64) -> Arch {
+		match (name, bits) {
+			("x86", _) => Arch::X86,
+			("arm", 64) => Arch::Arm64,
+			("arm", _) => Arch::Arm,
+			_ => Arch::Unknown
+		}
+	}
+
+	pub fn is_call(&self, opcode: &str) -> bool {
+		match self {
+			Arch::X86 => x86_call(opcode),
+			Arch::Arm | Arch::Arm64 => arm_call(opcode),
+			Arch::Unknown => x86_call(opcode) || arm_call(opcode)
+		}
+	}
+
+	/// Needs the operands too, ARM returns through ordinary loads and moves into pc
+	pub fn flow(&self, opcode: &str, operands: &str) -> Flow {
+		match self {
+			Arch::X86 => x86_flow(opcode),
+			Arch::Arm | Arch::Arm64 => arm_flow(opcode, operands),
+			Arch::Unknown => match x86_flow(opcode) {
+				Flow::Fallthrough => arm_flow(opcode, operands),
+				x => x
+			}
+		}
+	}
+}
+
+impl Metadata {
+	pub fn arch(&self) -> Arch {
+		Arch::new(&self.arch, self.bits)
+	}
+
+	pub fn ptr_size(&self) -> u64 {
+		(self.bits / 8).max(1)
+	}
+}
+
+// Prefixes rizin prints before the real opcode
+const PREFIXES: &[&str] = &["bnd", "notrack", "rep", "repe", "repz", "repne", "repnz", "lock"];
+
+/// Opcode and operands of a disassembled instruction, skipping prefixes
+pub fn split_instruction(inst: &str) -> (&str, String) {
+	let mut parts = inst.split_whitespace().skip_while(|x| PREFIXES.contains(x));
+	// A bare `rep` is its own opcode
+	let opcode = parts.next().or_else(|| inst.split_whitespace().last()).unwrap_or("");
+	(opcode, parts.collect::<Vec<_>>().join(" "))
+}
+
+fn x86_call(opcode: &str) -> bool {
+	matches!(opcode, "call" | "lcall")
+}
+
+fn x86_flow(opcode: &str) -> Flow {
+	match opcode {
+		"ret" | "retn" | "retf" | "retq" => Flow::Return,
+		"jmp" | "ljmp" => Flow::Jump,
+		// Jumping on a flag set or a zero counter goes with equal, like je, and the same way ARM's mi and vs do
+		"je" | "jz" | "jp" | "jpe" | "jo" | "js" | "jcxz" | "jecxz" | "jrcxz" | "loope" | "loopz" => Flow::Equal,
+		"jne" | "jnz" | "jnp" | "jpo" | "jno" | "jns" | "loop" | "loopne" | "loopnz" => Flow::NotEqual,
+		"ja" | "jnbe" | "jae" | "jnb" | "jnc" | "jg" | "jnle" | "jge" | "jnl" => Flow::Greater,
+		"jb" | "jnae" | "jc" | "jbe" | "jna" | "jl" | "jnge" | "jle" | "jng" => Flow::Less,
+		_ => Flow::Fallthrough
+	}
+}
+
+/// Thumb spells out the encoding width, `beq.w` is still `beq`
+fn strip_width(opcode: &str) -> &str {
+	opcode.strip_suffix(".w").or_else(|| opcode.strip_suffix(".n")).unwrap_or(opcode)
+}
+
+/// ARM condition codes, shared by `beq`, `b.eq`, `bxeq` and `bleq`
+fn arm_condition(cond: &str) -> Option<Flow> {
+	match cond {
+		"eq" | "mi" | "vs" => Some(Flow::Equal),
+		"ne" | "pl" | "vc" => Some(Flow::NotEqual),
+		"hs" | "cs" | "hi" | "ge" | "gt" => Some(Flow::Greater),
+		"lo" | "cc" | "ls" | "lt" | "le" => Some(Flow::Less),
+		"al" => Some(Flow::Jump),
+		_ => None
+	}
+}
+
+fn arm_call(opcode: &str) -> bool {
+	let opcode = strip_width(opcode);
+	matches!(opcode, "bl" | "blx" | "blr" | "blraa" | "blraaz" | "blrab" | "blrabz")
+		|| opcode.strip_prefix("blx").or_else(|| opcode.strip_prefix("bl")).and_then(arm_condition).is_some()
+}
+
+/// Whether pc is one of the registers written
+fn writes_pc(operands: &str) -> bool {
+	operands.split([',', ' ', '{', '}']).any(|x| x == "pc")
+}
+
+fn arm_flow(opcode: &str, operands: &str) -> Flow {
+	let opcode = strip_width(opcode);
+	let from_stack = operands.contains("sp");
+
+	match opcode {
+		"ret" | "retaa" | "retab" => Flow::Return,
+		"bx" if operands == "lr" => Flow::Return,
+		"mov" if operands.replace(' ', "") == "pc,lr" => Flow::Return,
+		// Popping pc off the stack is the usual ARMv7 epilogue
+		"pop" if writes_pc(operands) => Flow::Return,
+		"ldm" | "ldmia" | "ldmfd" if writes_pc(operands) => if from_stack { Flow::Return } else { Flow::Jump },
+		"ldr" if operands.starts_with("pc") => if from_stack { Flow::Return } else { Flow::Jump },
+		"b" | "br" | "bx" | "bxr" | "braa" | "braaz" | "brab" | "brabz" => Flow::Jump,
+		"cbz" | "tbz" => Flow::Equal,
+		"cbnz" | "tbnz" => Flow::NotEqual,
+		_ => opcode.strip_prefix("b.")
+			.or_else(|| opcode.strip_prefix("bx"))
+			.or_else(|| opcode.strip_prefix("b"))
+			.and_then(arm_condition)
+			.unwrap_or(Flow::Fallthrough)
+	}
+}
+
+/// Turns the last instruction of a block into a branch, `jump` is 0 when rizin couldn't resolve it
+pub fn get_branch_type(arch: Arch, inst: &str, jump: u64, fail: u64) -> Branch {
+	let (opcode, operands) = split_instruction(inst);
+
+	let fail = Dest::Known(fail);
+	let jump = if jump == 0 {
+		Dest::Unknown
+	} else {
+		Dest::Known(jump)
+	};
+
+	match arch.flow(opcode, &operands) {
+		Flow::Return => Branch::Return,
+		Flow::Jump => Branch::Neutral(jump),
+		Flow::Equal => Branch::Equality(jump, fail),
+		Flow::NotEqual => Branch::Equality(fail, jump),
+		Flow::Greater => Branch::Inequality(jump, fail),
+		Flow::Less => Branch::Inequality(fail, jump),
+		Flow::Fallthrough => Branch::Neutral(fail)
+	}
+}
+
+// Smaller immediates are loop counters, flags and struct offsets that every function has
+const MIN_CONSTANT: u64 = 0x100;
+
+/// Immediates an instruction uses as values, leaving out addresses, offsets and stack adjustments
+pub fn constants(arch: Arch, opcode: &str, operands: &str, code: (u64, u64)) -> Vec<u64> {
+	if arch.is_call(opcode) || arch.flow(opcode, operands) != Flow::Fallthrough || matches!(opcode, "adr" | "adrp" | "lea" | "push" | "pop") {
+		return Vec::new();
+	}
+
+	// Memory operands only hold offsets
+	let mut text = String::new();
+	let mut depth = 0;
+	for c in operands.chars() {
+		match c {
+			'[' | '{' => depth += 1,
+			']' | '}' => depth -= 1,
+			_ if depth == 0 => text.push(c),
+			_ => ()
+		}
+	}
+
+	let tokens: Vec<_> = text.split([',', ' ', '#']).filter(|x| !x.is_empty()).collect();
+	if tokens.iter().any(|x| matches!(*x, "sp" | "rsp" | "esp" | "rbp" | "ebp" | "fp" | "x29")) {
+		return Vec::new();
+	}
+
+	tokens.into_iter()
+		.filter_map(|x| match x.strip_prefix("0x") {
+			Some(x) => u64::from_str_radix(x, 16).ok(),
+			None => x.parse().ok()
+		})
+		.filter(|x| is_constant(*x, code))
+		.collect()
+}
+
+fn is_constant(x: u64, code: (u64, u64)) -> bool {
+	x >= MIN_CONSTANT && !(code.0..=code.1).contains(&x)
+}
+
+// Immediate and shift of `reg, #imm, lsl #shift`
+fn shifted_immediate(operands: &str) -> Option<(u64, u64)> {
+	let mut parts = operands.split(',').skip(1).map(|x| x.trim().trim_start_matches('#'));
+	let imm = parts.next()?;
+	let imm = match imm.strip_prefix("0x") {
+		Some(x) => u64::from_str_radix(x, 16).ok()?,
+		None => imm.parse().ok()?
+	};
+	let shift = match parts.next() {
+		Some(x) => x.strip_prefix("lsl")?.trim().trim_start_matches('#').parse().ok()?,
+		None => 0
+	};
+
+	(shift < 64).then_some((imm, shift))
+}
+
+/// AArch64 builds wide constants from a mov followed by movk's of the higher halves, this puts them back
+/// together. ARMv7 loads them from literal pools instead, which only show up as data xrefs
+#[derive(Default)]
+pub struct WideConstants {
+	// Register and the value built in it so far
+	partial: HashMap<String, u64>
+}
+
+impl WideConstants {
+	/// Constants one instruction uses, and the partial value it replaces when it completes one
+	pub fn step(&mut self, arch: Arch, opcode: &str, operands: &str, code: (u64, u64)) -> (Vec<u64>, Option<u64>) {
+		let found = constants(arch, opcode, operands, code);
+		if arch != Arch::Arm64 {
+			return (found, None);
+		}
+
+		let reg = operands.split(',').next().unwrap_or("").trim().to_string();
+		match (opcode, shifted_immediate(operands)) {
+			("mov" | "movz", Some((imm, shift))) => {
+				self.partial.insert(reg, imm << shift);
+				(found, None)
+			},
+			("movk", Some((imm, shift))) => match self.partial.get_mut(&reg) {
+				Some(value) => {
+					let old = *value;
+					*value = (old & !(0xffff << shift)) | (imm << shift);
+					(is_constant(*value, code).then_some(*value).into_iter().collect(), Some(old))
+				},
+				None => (found, None)
+			},
+			_ => {
+				self.partial.remove(&reg);
+				(found, None)
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const JUMP: u64 = 0x2000;
+	const FAIL: u64 = 0x1004;
+
+	fn branch(arch: Arch, inst: &str) -> Branch {
+		get_branch_type(arch, inst, JUMP, FAIL)
+	}
+
+	fn flows(arch: Arch, insts: &[&str], expected: Branch) {
+		for inst in insts {
+			assert_eq!(branch(arch, inst), expected, "{:?} {}", arch, inst);
+		}
+	}
+
+	fn equal() -> Branch {
+		Branch::Equality(Dest::Known(JUMP), Dest::Known(FAIL))
+	}
+
+	fn not_equal() -> Branch {
+		Branch::Equality(Dest::Known(FAIL), Dest::Known(JUMP))
+	}
+
+	fn greater() -> Branch {
+		Branch::Inequality(Dest::Known(JUMP), Dest::Known(FAIL))
+	}
+
+	fn less() -> Branch {
+		Branch::Inequality(Dest::Known(FAIL), Dest::Known(JUMP))
+	}
+
+	#[test]
+	fn arch_from_metadata() {
+		assert_eq!(Arch::new("x86", 64), Arch::X86);
+		assert_eq!(Arch::new("arm", 64), Arch::Arm64);
+		assert_eq!(Arch::new("arm", 16), Arch::Arm);
+		assert_eq!(Arch::new("mips", 32), Arch::Unknown);
+	}
+
+	#[test]
+	fn x86_branches() {
+		flows(Arch::X86, &["ret", "retn", "retf", "repz ret", "rep ret"], Branch::Return);
+		flows(Arch::X86, &["jmp 0x2000", "bnd jmp 0x2000", "notrack jmp rax"], Branch::Neutral(Dest::Known(JUMP)));
+		flows(Arch::X86, &["je 0x2000", "jz 0x2000", "jp 0x2000", "jo 0x2000", "js 0x2000", "jcxz 0x2000", "jecxz 0x2000", "jrcxz 0x2000", "loope 0x2000"], equal());
+		flows(Arch::X86, &["jne 0x2000", "jnz 0x2000", "jnp 0x2000", "jno 0x2000", "jns 0x2000", "loop 0x2000", "loopne 0x2000"], not_equal());
+		flows(Arch::X86, &["ja 0x2000", "jae 0x2000", "jnc 0x2000", "jg 0x2000", "jge 0x2000"], greater());
+		flows(Arch::X86, &["jb 0x2000", "jc 0x2000", "jbe 0x2000", "jl 0x2000", "jle 0x2000"], less());
+		flows(Arch::X86, &["mov eax, 1", "call 0x3000", "rep stosb"], Branch::Neutral(Dest::Known(FAIL)));
+	}
+
+	#[test]
+	fn arm64_branches() {
+		flows(Arch::Arm64, &["ret", "retaa", "retab", "ret x1"], Branch::Return);
+		flows(Arch::Arm64, &["b 0x2000", "br x16", "braaz x8"], Branch::Neutral(Dest::Known(JUMP)));
+		flows(Arch::Arm64, &["b.eq 0x2000", "b.mi 0x2000", "cbz x0, 0x2000", "tbz w1, 3, 0x2000"], equal());
+		flows(Arch::Arm64, &["b.ne 0x2000", "b.pl 0x2000", "cbnz w0, 0x2000", "tbnz x2, 0x3f, 0x2000"], not_equal());
+		flows(Arch::Arm64, &["b.hs 0x2000", "b.hi 0x2000", "b.ge 0x2000", "b.gt 0x2000"], greater());
+		flows(Arch::Arm64, &["b.lo 0x2000", "b.ls 0x2000", "b.lt 0x2000", "b.le 0x2000"], less());
+		flows(Arch::Arm64, &["bl 0x3000", "blr x8", "blraa x8, x9", "mov x0, x1"], Branch::Neutral(Dest::Known(FAIL)));
+	}
+
+	#[test]
+	fn arm_branches() {
+		flows(Arch::Arm, &["bx lr", "pop {r4, r5, pc}", "pop.w {r4, r5, r6, pc}", "ldr pc, [sp], 4", "ldm sp!, {r4, pc}", "mov pc, lr"], Branch::Return);
+		flows(Arch::Arm, &["b 0x2000", "b.w 0x2000", "b.n 0x2000", "bx r3", "ldr pc, [r3, r2, lsl 2]", "bal 0x2000"], Branch::Neutral(Dest::Known(JUMP)));
+		flows(Arch::Arm, &["beq 0x2000", "beq.w 0x2000", "bmi 0x2000", "bvs 0x2000", "cbz r0, 0x2000", "bxeq r3"], equal());
+		flows(Arch::Arm, &["bne 0x2000", "bne.n 0x2000", "bpl 0x2000", "bvc 0x2000", "cbnz r0, 0x2000"], not_equal());
+		flows(Arch::Arm, &["bhs 0x2000", "bcs 0x2000", "bhi 0x2000", "bge 0x2000", "bgt.w 0x2000"], greater());
+		flows(Arch::Arm, &["blo 0x2000", "bcc 0x2000", "bls 0x2000", "blt 0x2000", "ble.n 0x2000"], less());
+		flows(Arch::Arm, &["bl 0x3000", "blx r3", "bleq 0x3000", "pop {r4, r5}", "bic r0, r0, 3"], Branch::Neutral(Dest::Known(FAIL)));
+	}
+
+	#[test]
+	fn arm_calls() {
+		for opcode in ["bl", "blx", "blr", "blraaz", "bleq", "blxne", "blx.w"] {
+			assert!(Arch::Arm.is_call(opcode), "{}", opcode);
+		}
+		for opcode in ["b", "blt", "ble", "bls", "bic", "call"] {
+			assert!(!Arch::Arm.is_call(opcode), "{}", opcode);
+		}
+	}
+
+	#[test]
+	fn unresolved_jump() {
+		assert_eq!(get_branch_type(Arch::X86, "jmp rax", 0, FAIL), Branch::Neutral(Dest::Unknown));
+		assert_eq!(get_branch_type(Arch::Arm64, "cbz x0, x1", 0, FAIL), Branch::Equality(Dest::Unknown, Dest::Known(FAIL)));
+	}
+
+	#[test]
+	fn unknown_arch_uses_every_table() {
+		assert_eq!(branch(Arch::Unknown, "jne 0x2000"), not_equal());
+		assert_eq!(branch(Arch::Unknown, "b.ne 0x2000"), not_equal());
+		assert!(Arch::Unknown.is_call("call") && Arch::Unknown.is_call("bl"));
+	}
+
+	#[test]
+	fn constants_skip_addresses_and_offsets() {
+		let code = (0x1000, 0x5000);
+		assert_eq!(constants(Arch::X86, "mov", "eax, 0xdeadbeef", code), vec![0xdeadbeef]);
+		assert_eq!(constants(Arch::Arm64, "mov", "w0, #0x12345", code), vec![0x12345]);
+		assert!(constants(Arch::X86, "mov", "eax, 0x10", code).is_empty());
+		assert!(constants(Arch::X86, "mov", "eax, 0x2000", code).is_empty());
+		assert!(constants(Arch::X86, "mov", "eax, dword [rbx + 0x1234]", code).is_empty());
+		assert!(constants(Arch::X86, "sub", "rsp, 0x1000", code).is_empty());
+		assert!(constants(Arch::X86, "lea", "rdi, 0x9000", code).is_empty());
+		assert!(constants(Arch::X86, "jmp", "0x9000", code).is_empty());
+	}
+
+	#[test]
+	fn wide_constants_put_movk_halves_back_together() {
+		let code = (0x1000, 0x5000);
+		let mut wide = WideConstants::default();
+
+		assert_eq!(wide.step(Arch::Arm64, "mov", "w0, 0x5678", code), (vec![0x5678], None));
+		assert_eq!(wide.step(Arch::Arm64, "movk", "w0, 0x1234, lsl 16", code), (vec![0x12345678], Some(0x5678)));
+		assert_eq!(wide.step(Arch::Arm64, "movk", "x0, #0xbeef, lsl #32", code), (vec![0xbeef], None));
+		assert_eq!(wide.step(Arch::Arm64, "add", "w0, w0, 0x400", code), (vec![0x400], None));
+		assert_eq!(wide.step(Arch::Arm64, "movk", "w0, 0x9abc, lsl 16", code), (vec![0x9abc], None));
+		assert_eq!(wide.step(Arch::X86, "mov", "eax, 0x5678", code), (vec![0x5678], None));
+	}
+}
@@ -0,0 +1,91 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use colored::Colorize;
+
+use crate::util::*;
+use crate::db::*;
+use crate::analysis;
+
+struct Hop {
+	from: String,
+	to: String,
+	named: usize,
+	bound: usize
+}
+
+fn stem(path: &Path) -> String {
+	path.file_stem().map(|x| x.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// Symdb written for the hop between two consecutive exdbs
+fn hop_path(dir: &Path, from: &Path, to: &Path) -> PathBuf {
+	dir.join(format!("{}-{}.symdb", stem(from), stem(to)))
+}
+
+/// Matches each exdb against the next, naming every input after the previous hop's symdb
+pub fn run_chain(exdbs: &[PathBuf], dir: &Path, strategies: &[String], force: bool, show_lost: bool) {
+	assert!(exdbs.len() >= 2, "A chain needs at least two exdbs");
+
+	let mut hops = Vec::new();
+	let mut input = ExecDB::load(&exdbs[0]);
+	// Symbols that have been bound in every hop so far
+	let mut survivors: Option<HashSet<String>> = None;
+
+	for window in exdbs.windows(2) {
+		let (from, to) = (&window[0], &window[1]);
+		println!("{} {} -> {}", "Hop".bold(), stem(from).yellow(), stem(to).yellow());
+
+		let pair = ExecPair {
+			input,
+			output: ExecDB::load(to)
+		};
+
+		let named: HashSet<String> = pair.input.fns.values().filter_map(|x| x.name.clone()).collect();
+
+		let mut binds = BindDB::open(&hop_path(dir, from, to), &pair);
+		binds.check_pair(&pair, force);
+		analysis::run_strategies(&pair, &mut binds, strategies);
+
+		let bound: HashSet<String> = named.iter()
+			.filter(|x| binds.binds.get(*x).and_then(|x| x.get_addr()).is_some())
+			.cloned()
+			.collect();
+
+		if show_lost {
+			let mut lost: Vec<_> = named.difference(&bound).collect();
+			lost.sort();
+			lost.iter().for_each(|x| println!("  lost {}", demangle(x).red()));
+		}
+
+		hops.push(Hop {
+			from: stem(from),
+			to: stem(to),
+			named: named.len(),
+			bound: bound.len()
+		});
+
+		survivors = Some(match survivors {
+			Some(x) => x.intersection(&bound).cloned().collect(),
+			None => bound
+		});
+
+		// This hop's output is the next hop's input, named by what we just matched
+		let ExecPair { mut output, .. } = pair;
+		let applied = output.apply_names(&binds, false);
+		println!("Named {} functions in {}", applied.to_string().bright_green(), stem(to));
+		input = output;
+	}
+
+	println!();
+	for hop in &hops {
+		println!("{} -> {}: {} / {} symbols survived ({:.1}%)",
+			hop.from.yellow(),
+			hop.to.yellow(),
+			hop.bound.to_string().bright_green(),
+			hop.named,
+			hop.bound as f64 * 100.0 / hop.named.max(1) as f64
+		);
+	}
+
+	println!("{} symbols survived the whole chain", survivors.map(|x| x.len()).unwrap_or(0).to_string().bright_green());
+}
@@ -0,0 +1,175 @@
+use std::collections::{HashMap, HashSet};
+use colored::Colorize;
+
+use crate::util::*;
+use crate::db::*;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub enum IssueKind {
+	NotFunction,
+	SharedAddress,
+	CallContradiction,
+	PreviouslyRejected
+}
+
+pub struct Issue {
+	pub kind: IssueKind,
+	pub symbol: String,
+	pub message: String
+}
+
+impl IssueKind {
+	fn title(&self) -> &'static str {
+		match self {
+			IssueKind::NotFunction => "Bound to an address that isn't a function start",
+			IssueKind::SharedAddress => "Bound to the same address as another symbol",
+			IssueKind::CallContradiction => "Callers or callees disagree with verified binds",
+			IssueKind::PreviouslyRejected => "Bound to an address that was rejected before"
+		}
+	}
+}
+
+fn known_calls(func: &Function) -> HashSet<u64> {
+	func.blocks.iter()
+		.flat_map(|x| x.calls.iter().chain(match &x.branch {
+			Branch::TailCall(x) => Some(x),
+			_ => None
+		}))
+		.filter_map(|x| match x {
+			Dest::Known(x) => Some(*x),
+			_ => None
+		})
+		.collect()
+}
+
+fn verified_addr<'a>(binds: &BindDB, pair: &'a ExecPair, addr: u64) -> Option<(&'a String, u64)> {
+	let name = pair.input.fns.get(&addr)?.name.as_ref()?;
+	match binds.binds.get(name) {
+		Some(Bind::Verified(x)) => Some((name, *x)),
+		_ => None
+	}
+}
+
+/// Addresses each symbol has been rejected at according to the journal
+pub fn rejections(entries: &[JournalEntry]) -> HashMap<String, HashSet<u64>> {
+	let mut out: HashMap<String, HashSet<u64>> = HashMap::new();
+
+	for entry in entries {
+		if let Some(Bind::Not(x)) = &entry.new {
+			out.entry(entry.symbol.clone()).or_default().extend(x);
+		}
+	}
+
+	out
+}
+
+pub fn check(pair: &ExecPair, binds: &BindDB, rejected: &HashMap<String, HashSet<u64>>) -> Vec<Issue> {
+	let mut issues = Vec::new();
+	let base = pair.output.meta.base;
+
+	let mut by_addr: HashMap<u64, Vec<&String>> = HashMap::new();
+	for (symbol, bind) in &binds.binds {
+		if let Some(addr) = bind.get_addr() {
+			by_addr.entry(addr).or_default().push(symbol);
+		}
+	}
+
+	let input_fns: HashMap<&String, &Function> = pair.input.fns.values()
+		.filter_map(|x| (x.name.as_ref()?, x).as_some())
+		.collect();
+
+	for (symbol, bind) in &binds.binds {
+		let Some(addr) = bind.get_addr() else {
+			continue;
+		};
+
+		let Some(out_fn) = pair.output.fns.get(&addr) else {
+			issues.push(Issue {
+				kind: IssueKind::NotFunction,
+				symbol: symbol.clone(),
+				message: format!("{} is not a function in the output", addr.as_addr(base))
+			});
+			continue;
+		};
+
+		// Complete and base object ctors and dtors (C1/C2, D1/D2) are often the same code, and demangle the same
+		let name = demangle(symbol);
+		let others: Vec<_> = by_addr.get(&addr).into_iter().flatten()
+			.filter(|x| **x != symbol)
+			.map(|x| demangle(x))
+			.filter(|x| *x != name)
+			.collect();
+		if !others.is_empty() {
+			issues.push(Issue {
+				kind: IssueKind::SharedAddress,
+				symbol: symbol.clone(),
+				message: format!("{} is also bound to {}", addr.as_addr(base), others.join(", "))
+			});
+		}
+
+		if rejected.get(symbol).is_some_and(|x| x.contains(&addr)) {
+			issues.push(Issue {
+				kind: IssueKind::PreviouslyRejected,
+				symbol: symbol.clone(),
+				message: format!("{} was answered no earlier", addr.as_addr(base))
+			});
+		}
+
+		let Some(in_fn) = input_fns.get(symbol) else {
+			continue;
+		};
+
+		// Inlined callees legitimately vanish from the output, so only verified ones count
+		let out_calls = known_calls(out_fn);
+		let missing_callees: Vec<_> = known_calls(in_fn).into_iter()
+			.filter_map(|x| verified_addr(binds, pair, x))
+			.filter(|(_, x)| !out_calls.contains(x))
+			.map(|(x, _)| demangle(x))
+			.collect();
+
+		let out_callers: HashSet<_> = out_fn.xrefs.iter().map(|x| x.function_addr).collect();
+		let missing_callers: Vec<_> = in_fn.xrefs.iter()
+			.filter_map(|x| verified_addr(binds, pair, x.function_addr))
+			.filter(|(_, x)| !out_callers.contains(x))
+			.map(|(x, _)| demangle(x))
+			.collect();
+
+		if !missing_callees.is_empty() {
+			issues.push(Issue {
+				kind: IssueKind::CallContradiction,
+				symbol: symbol.clone(),
+				message: format!("doesn't call {}", missing_callees.join(", "))
+			});
+		}
+
+		if !missing_callers.is_empty() {
+			issues.push(Issue {
+				kind: IssueKind::CallContradiction,
+				symbol: symbol.clone(),
+				message: format!("isn't called by {}", missing_callers.join(", "))
+			});
+		}
+	}
+
+	issues.sort_by(|x, y| x.kind.cmp(&y.kind).then(x.symbol.cmp(&y.symbol)));
+	issues
+}
+
+pub fn print_issues(issues: &[Issue]) {
+	let mut last = None;
+
+	for issue in issues {
+		if last != Some(issue.kind) {
+			println!("{}", issue.kind.title().bold());
+			last = Some(issue.kind);
+		}
+
+		println!("  {} {}", demangle(&issue.symbol).yellow(), issue.message);
+	}
+
+	if issues.is_empty() {
+		println!("{}", "No problems found".bright_green());
+	} else {
+		println!("Found {} problems", issues.len().to_string().red());
+	}
+}
@@ -1,6 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::fmt::Display;
 use serde::{Serialize, Deserialize};
 
+use crate::format::*;
+use crate::util::AsHex;
+use colored::Colorize;
+
 // For Executable
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -10,7 +17,13 @@ pub enum Dest {
 	#[serde(rename = "K")]
 	Known(u64),
 	#[serde(rename = "U")]
-	Unknown
+	Unknown,
+	// Method slot called through a vtable, with the class when we know which vtable it was
+	#[serde(rename = "V")]
+	Virtual(Option<String>, u64),
+	// Imported function called through a PLT stub or GOT entry
+	#[serde(rename = "I")]
+	Import(String)
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
@@ -24,7 +37,13 @@ pub enum Branch {
 	Equality(Dest, Dest),
 	// A if greater than B
 	#[serde(rename = "I")]
-	Inequality(Dest, Dest)
+	Inequality(Dest, Dest),
+	// Jump table targets in case order
+	#[serde(rename = "S")]
+	Switch(Vec<Dest>),
+	// Jump to the start of another function
+	#[serde(rename = "C")]
+	TailCall(Dest)
 }
 
 #[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
@@ -47,6 +66,30 @@ pub struct StringRef {
 	pub xrefs: Vec<Address>
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename = "P")]
+pub struct Import {
+	#[serde(rename = "N")]
+	pub name: String,
+	// Address of the PLT stub, 0 when the import is only reached through the GOT
+	#[serde(rename = "A")]
+	pub plt: u64,
+	#[serde(rename = "X")]
+	pub xrefs: Vec<Address>
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename = "G")]
+pub struct Global {
+	// Symbol or flag name without the rizin prefix
+	#[serde(rename = "N")]
+	pub name: Option<String>,
+	#[serde(rename = "A")]
+	pub address: u64,
+	#[serde(rename = "X")]
+	pub xrefs: Vec<Address>
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(rename = "B")]
 pub struct Block {
@@ -57,7 +100,10 @@ pub struct Block {
 	#[serde(rename = "B")]
 	pub branch: Branch,
 	#[serde(rename = "S")]
-	pub strings: Vec<String>
+	pub strings: Vec<String>,
+	// Immediates used as values, the same on every ISA
+	#[serde(rename = "K", default)]
+	pub constants: Vec<u64>
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -84,14 +130,40 @@ pub struct Vtable {
 	pub function_addrs: Vec<u64>
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename = "M")]
+pub struct Metadata {
+	#[serde(rename = "P")]
+	pub path: String,
+	// sha1 of the binary, or md5 if rizin has no sha1
+	#[serde(rename = "H")]
+	pub hash: String,
+	#[serde(rename = "A")]
+	pub arch: String,
+	#[serde(rename = "W")]
+	pub bits: u64,
+	#[serde(rename = "B")]
+	pub base: u64,
+	#[serde(rename = "T")]
+	pub format: String,
+	#[serde(rename = "I")]
+	pub build_id: Option<String>
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct ExecDB {
+	#[serde(rename = "M", default)]
+	pub meta: Metadata,
 	#[serde(rename = "F")]
 	pub fns: HashMap<u64, Function>,
 	#[serde(rename = "V")]
 	pub vtables: HashMap<String, Vtable>,
 	#[serde(rename = "S")]
-	pub strings: HashMap<String, StringRef>
+	pub strings: HashMap<String, StringRef>,
+	#[serde(rename = "I", default)]
+	pub imports: HashMap<String, Import>,
+	#[serde(rename = "G", default)]
+	pub globals: HashMap<u64, Global>
 }
 
 pub struct ExecPair {
@@ -109,18 +181,367 @@ pub enum Bind {
 	Inline
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PairIdentity {
+	pub input: String,
+	pub output: String
+}
+
+#[derive(Serialize, Deserialize, Default)]
 pub struct BindDB {
-	pub binds: HashMap<String, Bind>
+	#[serde(default)]
+	pub version: u32,
+	// Hashes of the binaries this symdb was matched against
+	#[serde(default)]
+	pub identity: Option<PairIdentity>,
+	// Image base of the output binary that bound addresses are relative to
+	#[serde(default)]
+	pub base: Option<u64>,
+	pub binds: HashMap<String, Bind>,
+	// Strategy or command that last set each bind
+	#[serde(default)]
+	pub sources: HashMap<String, String>,
+	// Number of journal entries already reflected in the snapshot
+	#[serde(default)]
+	pub seq: usize,
+	// Changes not yet appended to the journal
+	#[serde(skip)]
+	pending: Vec<JournalEntry>,
+	#[serde(skip)]
+	pub format: SymFormat,
+	#[serde(skip)]
+	path: Option<PathBuf>,
+	#[serde(skip)]
+	run: u64,
+	#[serde(skip)]
+	last_save: u64,
+	#[serde(skip)]
+	last_flush: u64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JournalEntry {
+	pub run: u64,
+	pub time: u64,
+	pub symbol: String,
+	pub old: Option<Bind>,
+	pub new: Option<Bind>,
+	pub source: String
+}
+
+impl Display for Metadata {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		writeln!(f, "path:     {}", self.path)?;
+		writeln!(f, "hash:     {}", self.hash)?;
+		writeln!(f, "format:   {}", self.format)?;
+		writeln!(f, "arch:     {} ({} bit)", self.arch, self.bits)?;
+		writeln!(f, "base:     {:#x}", self.base)?;
+		write!(f, "build id: {}", self.build_id.as_deref().unwrap_or("-"))
+	}
+}
+
+impl Dest {
+	fn shift(&mut self, delta: u64) {
+		if let Dest::Known(x) = self {
+			*x = x.wrapping_add(delta);
+		}
+	}
+}
+
+impl Branch {
+	fn shift(&mut self, delta: u64) {
+		match self {
+			Branch::Return => (),
+			Branch::Neutral(x) | Branch::TailCall(x) => x.shift(delta),
+			Branch::Switch(x) => x.iter_mut().for_each(|x| x.shift(delta)),
+			Branch::Equality(x, y) | Branch::Inequality(x, y) => {
+				x.shift(delta);
+				y.shift(delta);
+			}
+		}
+	}
+}
+
+impl Address {
+	fn shift(&mut self, delta: u64) {
+		self.addr = self.addr.wrapping_add(delta);
+		self.block_addr = self.block_addr.wrapping_add(delta);
+		self.function_addr = self.function_addr.wrapping_add(delta);
+	}
+}
+
+impl Vtable {
+	/// Rizin reports the address point, the first method slot, which sits after the offset to top
+	/// and typeinfo pointers. That's what code loads into objects, the vtable symbol is at the start
+	pub fn start(&self, ptr_size: u64) -> u64 {
+		self.address.wrapping_sub(2 * ptr_size)
+	}
 }
 
 impl ExecDB {
+	/// Moves every address to a new image base
+	pub fn rebase(&mut self, new_base: u64) {
+		let delta = new_base.wrapping_sub(self.meta.base);
+
+		self.fns = std::mem::take(&mut self.fns).into_iter().map(|(addr, mut func)| {
+			func.address.shift(delta);
+			func.xrefs.iter_mut().for_each(|x| x.shift(delta));
+
+			for block in &mut func.blocks {
+				block.address.shift(delta);
+				block.branch.shift(delta);
+				block.calls.iter_mut().for_each(|x| x.shift(delta));
+			}
+
+			(addr.wrapping_add(delta), func)
+		}).collect();
+
+		for vtable in self.vtables.values_mut() {
+			vtable.address = vtable.address.wrapping_add(delta);
+			vtable.function_addrs.iter_mut().for_each(|x| *x = x.wrapping_add(delta));
+		}
+
+		for string in self.strings.values_mut() {
+			string.xrefs.iter_mut().for_each(|x| x.shift(delta));
+		}
+
+		self.globals = std::mem::take(&mut self.globals).into_values().map(|mut global| {
+			global.address = global.address.wrapping_add(delta);
+			global.xrefs.iter_mut().for_each(|x| x.shift(delta));
+			(global.address, global)
+		}).collect();
+
+		for import in self.imports.values_mut() {
+			if import.plt != 0 {
+				import.plt = import.plt.wrapping_add(delta);
+			}
+			import.xrefs.iter_mut().for_each(|x| x.shift(delta));
+		}
+
+		self.meta.base = new_base;
+	}
+
+	/// Names functions after the symbols bound to them, returns how many were named
+	pub fn apply_names(&mut self, binds: &BindDB, verified_only: bool) -> usize {
+		// Symbols sharing an address go by verified first, then name, so the same symdb always names a function the same
+		let mut candidates: Vec<_> = binds.binds.iter()
+			.filter(|(_, x)| !verified_only || matches!(x, Bind::Verified(_)))
+			.filter_map(|(x, y)| y.get_addr().map(|addr| (!matches!(y, Bind::Verified(_)), x, addr)))
+			.collect();
+		candidates.sort();
+
+		let mut named = HashSet::new();
+		for (_, symbol, addr) in candidates {
+			let Some(func) = self.fns.get_mut(&addr) else {
+				continue;
+			};
+			if named.insert(addr) {
+				func.name = Some(symbol.clone());
+			}
+		}
+
+		named.len()
+	}
+
 	pub fn addr_to_block(&self, addr: &Address) -> Option<&Block> {
 		self.fns.get(&addr.function_addr)?.blocks.iter()
 			.find(|x| x.address.block_addr == addr.block_addr)
 	}
 }
 
+// Snapshots are rewritten at most this often (ms), the journal covers everything in between
+const SAVE_INTERVAL: u64 = 5000;
+// Buffered journal entries are appended at most this often (ms)
+const JOURNAL_INTERVAL: u64 = 500;
+
+impl BindDB {
+	fn record(&mut self, symbol: &str, old: Option<Bind>, new: Option<Bind>, source: &str) {
+		if old == new {
+			return;
+		}
+
+		let time = crate::journal::now();
+		if self.run == 0 {
+			self.run = time;
+		}
+
+		self.pending.push(JournalEntry {
+			run: self.run,
+			time,
+			symbol: symbol.to_string(),
+			old,
+			new,
+			source: source.to_string()
+		});
+	}
+
+	/// Appends buffered changes to the journal
+	pub fn flush_journal(&mut self) {
+		if let Some(path) = &self.path {
+			crate::journal::append(path, &self.pending);
+			self.seq += self.pending.len();
+			self.pending.clear();
+		}
+		self.last_flush = crate::journal::now();
+	}
+
+	pub fn set(&mut self, symbol: &str, bind: Bind, source: &str) {
+		let old = self.binds.insert(symbol.to_string(), bind.clone());
+		if old.as_ref() != Some(&bind) {
+			self.sources.insert(symbol.to_string(), source.to_string());
+		}
+		self.record(symbol, old, Some(bind), source);
+	}
+
+	pub fn remove(&mut self, symbol: &str, source: &str) {
+		let old = self.binds.remove(symbol);
+		self.sources.remove(symbol);
+		self.record(symbol, old, None, source);
+	}
+
+	/// Ties the database to a file, journaling any changes made so far
+	pub fn attach(&mut self, path: &Path) {
+		self.seq = crate::journal::read(path).len();
+		self.path = Some(path.to_path_buf());
+		self.flush_journal();
+	}
+
+	/// Loads a snapshot and replays any journal entries it is missing
+	pub fn load(path: &Path) -> BindDB {
+		let data = std::fs::read(path).expect("Unable to read symdb file");
+		let (mut db, format) = decode_symdb(&data).unwrap_or_else(|e| panic!("Unable to load {}: {}", path.display(), e));
+		db.format = format;
+
+		let journal = crate::journal::read(path);
+		let missing = journal.get(db.seq..).unwrap_or_default();
+		if !missing.is_empty() {
+			println!("Recovering {} unsaved changes", missing.len());
+		}
+
+		for entry in missing {
+			match &entry.new {
+				Some(x) => {
+					db.binds.insert(entry.symbol.clone(), x.clone());
+					if entry.source != "rebase" {
+						db.sources.insert(entry.symbol.clone(), entry.source.clone());
+					}
+				},
+				None => {
+					db.binds.remove(&entry.symbol);
+					db.sources.remove(&entry.symbol);
+				}
+			};
+		}
+
+		db.seq = journal.len();
+		db.path = Some(path.to_path_buf());
+		db.last_save = crate::journal::now();
+		db
+	}
+
+	/// Loads the symdb at `path`, or starts a new one from the pair's vtables
+	pub fn open(path: &Path, pair: &ExecPair) -> BindDB {
+		if path.exists() {
+			BindDB::load(path)
+		} else {
+			let mut db = BindDB::new(pair);
+			db.format = SymFormat::from_path(path);
+			db.attach(path);
+			db
+		}
+	}
+
+	/// Records the pair's identity, or refuses a pair other than the recorded one unless forced
+	pub fn check_pair(&mut self, pair: &ExecPair, force: bool) {
+		match self.base {
+			Some(x) if x != pair.output.meta.base => {
+				println!("{}", format!(
+					"symdb addresses are based at {:#x} but the output exdb is at {:#x}, use `symbo rebase` to match them",
+					x, pair.output.meta.base
+				).red());
+
+				if !force {
+					std::process::exit(1);
+				}
+			},
+			Some(_) => (),
+			None => self.base = Some(pair.output.meta.base)
+		}
+
+		if pair.input.meta.hash.is_empty() || pair.output.meta.hash.is_empty() {
+			println!("{}", "Exdb has no binary hash, regenerate it to enable pair checks".yellow());
+			return;
+		}
+
+		let current = PairIdentity {
+			input: pair.input.meta.hash.clone(),
+			output: pair.output.meta.hash.clone()
+		};
+
+		match &self.identity {
+			Some(x) if *x != current => {
+				println!("{}", "symdb was built against a different input/output pair".red());
+				println!("  input:  {} (symdb) vs {} ({})", x.input, current.input, pair.input.meta.path);
+				println!("  output: {} (symdb) vs {} ({})", x.output, current.output, pair.output.meta.path);
+
+				if !force {
+					println!("Use --force to continue anyway");
+					std::process::exit(1);
+				}
+			},
+			Some(_) => (),
+			None => self.identity = Some(current)
+		}
+	}
+
+	/// Moves every bound address to a new image base. Symdbs from before bases were recorded need one set first.
+	pub fn rebase(&mut self, new_base: u64) {
+		let old_base = self.base.expect("symdb has no recorded image base, its addresses can't be moved");
+		let delta = new_base.wrapping_sub(old_base);
+		let binds: Vec<_> = self.binds.iter().map(|(x, y)| (x.clone(), y.shifted(delta))).collect();
+
+		// Not through set() so each bind keeps the source that found it
+		for (symbol, bind) in binds {
+			let old = self.binds.insert(symbol.clone(), bind.clone());
+			self.record(&symbol, old, Some(bind), "rebase");
+		}
+		self.base = Some(new_base);
+	}
+
+	/// Writes the snapshot if the last one is older than SAVE_INTERVAL, and buffered changes to the journal every JOURNAL_INTERVAL
+	pub fn checkpoint(&mut self) {
+		let now = crate::journal::now();
+		if now.saturating_sub(self.last_save) >= SAVE_INTERVAL {
+			self.save();
+		} else if now.saturating_sub(self.last_flush) >= JOURNAL_INTERVAL {
+			self.flush_journal();
+		}
+	}
+
+	/// Writes the snapshot and compacts the journal behind it
+	pub fn save(&mut self) {
+		let path = self.path.clone().expect("symdb is not attached to a file");
+		self.flush_journal();
+
+		// Snapshot first, replaying any suffix of the old journal over it still ends up in the same state
+		let journal = crate::journal::compact(crate::journal::read(&path));
+		self.seq = journal.len();
+		self.version = SYMDB_VERSION;
+		write_atomic(&path, &encode_symdb(self));
+		crate::journal::rewrite(&path, &journal);
+		self.last_save = crate::journal::now();
+	}
+}
+
+/// Writes through a temporary file in the same directory so readers never see a partial file
+pub fn write_atomic(path: &Path, data: &[u8]) {
+	let dir = path.parent().filter(|x| !x.as_os_str().is_empty()).unwrap_or(Path::new("."));
+
+	let mut tmp = tempfile::NamedTempFile::new_in(dir).expect("Unable to create temporary file");
+	tmp.write_all(data).expect("Unable to write temporary file");
+	tmp.persist(path).expect("Unable to replace file");
+}
+
 impl Bind {
 	pub fn get_addr(&self) -> Option<u64> {
 		match self {
@@ -130,3 +551,24 @@ impl Bind {
 		}
 	}
 }
+
+impl Bind {
+	/// Human readable form, with addresses relative to `base` when showing RVAs
+	pub fn describe(&self, base: u64) -> String {
+		match self {
+			Bind::Verified(x) => format!("verified {}", x.as_addr(base)),
+			Bind::Unverified(x) => format!("unverified {}", x.as_addr(base)),
+			Bind::Not(x) => format!("not [{}]", x.iter().map(|x| x.as_addr(base)).collect::<Vec<_>>().join(", ")),
+			Bind::Inline => "inline".to_string()
+		}
+	}
+
+	pub fn shifted(&self, delta: u64) -> Bind {
+		match self {
+			Bind::Verified(x) => Bind::Verified(x.wrapping_add(delta)),
+			Bind::Unverified(x) => Bind::Unverified(x.wrapping_add(delta)),
+			Bind::Not(x) => Bind::Not(x.iter().map(|x| x.wrapping_add(delta)).collect()),
+			Bind::Inline => Bind::Inline
+		}
+	}
+}
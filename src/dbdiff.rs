@@ -0,0 +1,87 @@
+use std::collections::BTreeMap;
+use colored::Colorize;
+
+use crate::util::*;
+use crate::db::*;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Change {
+	Added,
+	Removed,
+	NewlyVerified,
+	ChangedAddress,
+	MovedToNot,
+	Other
+}
+
+impl Change {
+	fn title(&self) -> &'static str {
+		match self {
+			Change::Added => "Added",
+			Change::Removed => "Removed",
+			Change::NewlyVerified => "Newly verified",
+			Change::ChangedAddress => "Changed address",
+			Change::MovedToNot => "Moved into not",
+			Change::Other => "Other changes"
+		}
+	}
+}
+
+/// Symbol, old bind and new bind for each kind of change
+pub type Changes = BTreeMap<Change, Vec<(String, Option<Bind>, Option<Bind>)>>;
+
+fn classify(old: Option<&Bind>, new: Option<&Bind>) -> Option<Change> {
+	match (old, new) {
+		(x, y) if x == y => None,
+		(_, None) => Some(Change::Removed),
+		(Some(Bind::Not(_)), Some(Bind::Not(_))) => Some(Change::Other),
+		(_, Some(Bind::Not(_))) => Some(Change::MovedToNot),
+		(None, _) => Some(Change::Added),
+		(Some(x), Some(Bind::Verified(_))) if !matches!(x, Bind::Verified(_)) => Some(Change::NewlyVerified),
+		(Some(x), Some(y)) if x.get_addr().is_some() && y.get_addr().is_some() && x.get_addr() != y.get_addr() => Some(Change::ChangedAddress),
+		_ => Some(Change::Other)
+	}
+}
+
+/// Every symbol whose bind differs, grouped by the kind of change
+pub fn diff(old: &BindDB, new: &BindDB) -> Changes {
+	// Compare at the old symdb's base so a rebase alone isn't a change, a symdb without one is taken as it is
+	let delta = match (old.base, new.base) {
+		(Some(x), Some(y)) => x.wrapping_sub(y),
+		_ => 0
+	};
+	let new_binds: BTreeMap<_, _> = new.binds.iter().map(|(x, y)| (x, y.shifted(delta))).collect();
+
+	let mut symbols: Vec<_> = old.binds.keys().chain(new.binds.keys()).collect();
+	symbols.sort();
+	symbols.dedup();
+
+	let mut changes = Changes::new();
+	for symbol in symbols {
+		let (a, b) = (old.binds.get(symbol), new_binds.get(symbol));
+		if let Some(change) = classify(a, b) {
+			changes.entry(change).or_default().push((symbol.clone(), a.cloned(), b.cloned()));
+		}
+	}
+
+	changes
+}
+
+pub fn print_diff(changes: &Changes, base: u64) {
+	let describe = |x: &Option<Bind>| x.as_ref().map(|x| x.describe(base)).unwrap_or("none".to_string());
+
+	for (change, symbols) in changes {
+		println!("{} ({})", change.title().bold(), symbols.len());
+		for (symbol, old, new) in symbols {
+			match change {
+				Change::Added => println!("  {} {} {}", "+".bright_green(), demangle(symbol).yellow(), describe(new)),
+				Change::Removed => println!("  {} {} {}", "-".red(), demangle(symbol).yellow(), describe(old)),
+				_ => println!("  {} {} {} -> {}", "~".blue(), demangle(symbol).yellow(), describe(old), describe(new))
+			}
+		}
+	}
+
+	if changes.is_empty() {
+		println!("No differences");
+	}
+}
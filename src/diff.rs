@@ -0,0 +1,181 @@
+use std::collections::{HashMap, BTreeSet};
+use colored::{Colorize, ColoredString};
+
+use crate::util::*;
+use crate::db::*;
+
+const COLUMN: usize = 56;
+
+// One side of a diff row. Empty means the other side has nothing to pair with.
+type Cell = Option<String>;
+
+fn branch_kind(branch: &Branch) -> &'static str {
+	match branch {
+		Branch::Return => "ret",
+		Branch::Neutral(_) => "jmp",
+		Branch::Equality(_, _) => "eq",
+		Branch::Inequality(_, _) => "ineq",
+		Branch::Switch(_) => "switch",
+		Branch::TailCall(_) => "tail"
+	}
+}
+
+fn fit(text: &str) -> String {
+	let mut out: String = text.chars().take(COLUMN).collect();
+	if text.chars().count() > COLUMN {
+		out.pop();
+		out.push('…');
+	}
+	format!("{:<width$}", out, width = COLUMN)
+}
+
+fn paint(text: String, same: bool) -> ColoredString {
+	if same {
+		text.green()
+	} else {
+		text.red()
+	}
+}
+
+fn print_row(left: Cell, right: Cell) {
+	let same = left == right;
+	let left = fit(left.as_deref().unwrap_or(""));
+	let right = fit(right.as_deref().unwrap_or(""));
+
+	println!("{} {} {}", paint(left, same), "│".dimmed(), paint(right, same));
+}
+
+fn print_header(left: &str, right: &str) {
+	println!("{} {} {}", fit(left).bold(), "│".dimmed(), fit(right).bold());
+}
+
+// Pairs up two lists positionally, padding the shorter one
+fn zip_longest<T: Clone>(a: &[T], b: &[T]) -> Vec<(Option<T>, Option<T>)> {
+	(0..a.len().max(b.len()))
+		.map(|i| (a.get(i).cloned(), b.get(i).cloned()))
+		.collect()
+}
+
+struct Namer<'a> {
+	pair: &'a ExecPair,
+	bound: HashMap<u64, &'a String>
+}
+
+impl<'a> Namer<'a> {
+	fn new(pair: &'a ExecPair, binds: &'a BindDB) -> Self {
+		Namer {
+			pair,
+			bound: binds.binds.iter()
+				.filter_map(|(x, y)| (y.get_addr()?, x).as_some())
+				.collect()
+		}
+	}
+
+	fn input(&self, addr: u64) -> String {
+		self.pair.input.fns.get(&addr)
+			.and_then(|x| x.name.as_ref())
+			.map(|x| demangle(x))
+			.unwrap_or_else(|| addr.as_addr(self.pair.input.meta.base))
+	}
+
+	fn output(&self, addr: u64) -> String {
+		self.bound.get(&addr)
+			.map(|x| demangle(x))
+			.unwrap_or_else(|| addr.as_addr(self.pair.output.meta.base))
+	}
+
+	fn input_dest(&self, dest: &Dest) -> String {
+		match dest {
+			Dest::Known(x) => self.input(*x),
+			x => other_dest(x)
+		}
+	}
+
+	fn output_dest(&self, dest: &Dest) -> String {
+		match dest {
+			Dest::Known(x) => self.output(*x),
+			x => other_dest(x)
+		}
+	}
+}
+
+// Destinations that read the same on both sides
+fn other_dest(dest: &Dest) -> String {
+	match dest {
+		Dest::Virtual(Some(class), slot) => format!("{} slot {}", demangle(class), slot),
+		Dest::Virtual(None, slot) => format!("virtual slot {}", slot),
+		Dest::Import(x) => format!("{} (import)", demangle(x)),
+		_ => "?".to_string()
+	}
+}
+
+fn sorted_blocks(func: &Function) -> Vec<&Block> {
+	let mut blocks: Vec<_> = func.blocks.iter().collect();
+	blocks.sort_by_key(|x| x.address.block_addr);
+	blocks
+}
+
+/// Prints the input function bound to `symbol` next to the output function at `addr`
+pub fn print_fn_diff(pair: &ExecPair, binds: &BindDB, symbol: &str, addr: u64) {
+	let namer = Namer::new(pair, binds);
+	let (in_base, out_base) = (pair.input.meta.base, pair.output.meta.base);
+
+	let in_fn = pair.input.fns.values().find(|x| x.name.as_deref() == Some(symbol));
+	let out_fn = pair.output.fns.get(&addr);
+
+	let (in_fn, out_fn) = match (in_fn, out_fn) {
+		(Some(x), Some(y)) => (x, y),
+		(None, _) => return println!("{} is not an input function", demangle(symbol).yellow()),
+		(_, None) => return println!("{} is not an output function", addr.as_addr(out_base).blue())
+	};
+
+	print_header(
+		&format!("{} {}", in_fn.address.function_addr.as_addr(in_base), demangle(symbol)),
+		&format!("{} {}", addr.as_addr(out_base), namer.output(addr))
+	);
+
+	let in_blocks = sorted_blocks(in_fn);
+	let out_blocks = sorted_blocks(out_fn);
+
+	for (i, o) in zip_longest(&in_blocks, &out_blocks) {
+		// Block addresses never agree between binaries, so only the branch kind is compared
+		let left = i.map(|x| format!("{:<5}@ {}", branch_kind(&x.branch), x.address.block_addr.as_addr(in_base)));
+		let right = o.map(|x| format!("{:<5}@ {}", branch_kind(&x.branch), x.address.block_addr.as_addr(out_base)));
+		let same = i.map(|x| branch_kind(&x.branch)) == o.map(|x| branch_kind(&x.branch));
+
+		println!("{} {} {}",
+			paint(fit(left.as_deref().unwrap_or("")), same).bold(),
+			"│".dimmed(),
+			paint(fit(right.as_deref().unwrap_or("")), same).bold()
+		);
+
+		let in_calls: Vec<_> = i.map(|x| x.calls.iter().map(|x| namer.input_dest(x)).collect()).unwrap_or_default();
+		let out_calls: Vec<_> = o.map(|x| x.calls.iter().map(|x| namer.output_dest(x)).collect()).unwrap_or_default();
+		for (l, r) in zip_longest(&in_calls, &out_calls) {
+			print_row(l.map(|x| format!("  call {}", x)), r.map(|x| format!("  call {}", x)));
+		}
+
+		let in_strings: Vec<_> = i.map(|x| x.strings.clone()).unwrap_or_default();
+		let out_strings: Vec<_> = o.map(|x| x.strings.clone()).unwrap_or_default();
+		for (l, r) in zip_longest(&in_strings, &out_strings) {
+			print_row(l.map(|x| format!("  str {:?}", x)), r.map(|x| format!("  str {:?}", x)));
+		}
+
+		let in_constants: Vec<_> = i.map(|x| x.constants.clone()).unwrap_or_default();
+		let out_constants: Vec<_> = o.map(|x| x.constants.clone()).unwrap_or_default();
+		for (l, r) in zip_longest(&in_constants, &out_constants) {
+			print_row(l.map(|x| format!("  const {}", x.as_hex())), r.map(|x| format!("  const {}", x.as_hex())));
+		}
+	}
+
+	let in_callers: BTreeSet<_> = in_fn.xrefs.iter().map(|x| namer.input(x.function_addr)).collect();
+	let out_callers: BTreeSet<_> = out_fn.xrefs.iter().map(|x| namer.output(x.function_addr)).collect();
+
+	print_header(&format!("{} callers", in_callers.len()), &format!("{} callers", out_callers.len()));
+	for caller in in_callers.union(&out_callers) {
+		print_row(
+			in_callers.contains(caller).then(|| format!("  {}", caller)),
+			out_callers.contains(caller).then(|| format!("  {}", caller))
+		);
+	}
+}
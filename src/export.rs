@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+use serde::{Serialize, Deserialize};
+
+use crate::db::*;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+	/// `symbol,address,state` rows
+	Csv,
+	/// Object of symbol to address
+	Json,
+	/// Rizin script defining a flag per symbol
+	Rizin
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ExportTarget {
+	pub path: PathBuf,
+	pub format: ExportFormat,
+	/// Write addresses relative to the symdb's image base
+	#[serde(default)]
+	pub rva: bool,
+	#[serde(default)]
+	pub verified_only: bool
+}
+
+/// Writes every bound symbol to the target, returns how many were written
+pub fn export(binds: &BindDB, target: &ExportTarget) -> usize {
+	let base = if target.rva { binds.base.unwrap_or(0) } else { 0 };
+
+	let mut symbols: Vec<_> = binds.binds.iter()
+		.filter(|(_, x)| !target.verified_only || matches!(x, Bind::Verified(_)))
+		.filter_map(|(x, y)| Some((x, y.get_addr()?.wrapping_sub(base), matches!(y, Bind::Verified(_)))))
+		.collect();
+	symbols.sort();
+
+	let out = match target.format {
+		ExportFormat::Csv => symbols.iter()
+			.map(|(x, y, z)| format!("{},{:#x},{}\n", x, y, if *z { "verified" } else { "unverified" }))
+			.collect::<String>(),
+		ExportFormat::Json => serde_json::to_string_pretty(
+			&symbols.iter().map(|(x, y, _)| (x.to_string(), *y)).collect::<std::collections::BTreeMap<_, _>>()
+		).unwrap(),
+		ExportFormat::Rizin => symbols.iter()
+			.map(|(x, y, _)| format!("f sym.{} @ {:#x}\n", x, y))
+			.collect::<String>()
+	};
+
+	write_atomic(&target.path, out.as_bytes());
+	symbols.len()
+}
@@ -1,4 +1,3 @@
-use std::path::Path;
 use std::collections::HashMap;
 use colored::Colorize;
 use crate::util::*;
@@ -10,7 +9,18 @@ fn subset_of<T>(a: &[T], b: &[T]) -> bool where T: PartialEq + std::clone::Clone
 	)
 }
 
-fn find_symbols(pair: &ExecPair, binds: &mut BindDB, symbol: String, candidates: &HashMap<u64, Function>) {
+fn reject(binds: &mut BindDB, symbol: &str, candidate: u64) {
+	match binds.binds.get(symbol).cloned() {
+		Some(Bind::Not(mut x)) => {
+			x.push(candidate);
+			binds.set(symbol, Bind::Not(x), "find");
+		},
+		_ => binds.set(symbol, Bind::Not(vec![candidate]), "find")
+	}
+}
+
+/// Narrows `candidates` down by callers and callees already bound, asking about the few left
+pub fn find_symbols(pair: &ExecPair, binds: &mut BindDB, symbol: String, candidates: &HashMap<u64, Function>) {
 	let threshold = 10;
 
 	let input_fn = pair.input.fns.iter().find(|(_,x)| x.name.as_ref() == Some(&symbol)).expect("Symbol not found");
@@ -24,24 +34,22 @@ fn find_symbols(pair: &ExecPair, binds: &mut BindDB, symbol: String, candidates:
 		.filter_map(|(x, y)| y.get_addr().map(|y| (y, x.to_string())))
 		.collect::<HashMap<_, _>>();
 
-	if let Some(bind) = binds.binds.get_mut(&symbol) {
-		match bind {
-			Bind::Verified(x) => {
-				println!("{} is already verified at {:#x}", symbol.bright_green(), x);
+	match binds.binds.get(&symbol).cloned() {
+		Some(Bind::Verified(x)) => {
+			println!("{} is already verified at {}", symbol.bright_green(), x.as_addr(pair.output.meta.base));
+			return;
+		},
+
+		Some(Bind::Unverified(x)) => {
+			if conflict_confirm(pair, binds, &symbol, x) == Some(true) {
+				binds.set(&symbol, Bind::Verified(x), "find");
 				return;
-			},
-
-			Bind::Unverified(x) => {
-				if conflict_confirm(&symbol, *x) == Some(true) {
-					*bind = Bind::Verified(*x);
-					return;
-				} else {
-					*bind = Bind::Not(vec![*x]);
-				}
-			},
-
-			_ => ()
-		}
+			} else {
+				binds.set(&symbol, Bind::Not(vec![x]), "find");
+			}
+		},
+
+		_ => ()
 	}
 
 	let mut verified_xrefs: Vec<_> = input_fn.1.xrefs.iter()
@@ -71,19 +79,16 @@ fn find_symbols(pair: &ExecPair, binds: &mut BindDB, symbol: String, candidates:
 
 	if candidates.len() <= threshold {
 		for candidate in candidates {
-			if binds_reversed_ver.get(&candidate).is_some() {
+			if binds_reversed_ver.contains_key(&candidate) {
 				continue;
 			}
 
-			if conflict_confirm(&symbol, candidate) == Some(true) {
-				binds.binds.insert(symbol.clone(), Bind::Verified(candidate));
+			if conflict_confirm(pair, binds, &symbol, candidate) == Some(true) {
+				binds.set(&symbol, Bind::Verified(candidate), "find");
 				binds_reversed_ver.insert(candidate, symbol.clone());
 				return;
 			} else {
-				match binds.binds.get_mut(&symbol) {
-					Some(Bind::Not(x)) => x.push(candidate),
-					_ => { binds.binds.insert(symbol.clone(), Bind::Not(vec![candidate])); }
-				}
+				reject(binds, &symbol, candidate);
 			}
 		}
 
@@ -93,11 +98,10 @@ fn find_symbols(pair: &ExecPair, binds: &mut BindDB, symbol: String, candidates:
 	println!("Checking Calls");
 
 	let mut verified_calls = input_fn.1.blocks.iter()
-		.map(|x| x.calls.clone())
-		.flatten()
+		.flat_map(|x| x.calls.clone())
 		.filter_map(|x| match x {
 			Dest::Known(x) => Some(x),
-			Dest::Unknown => None
+			_ => None
 		})
 		.filter_map(|x| pair.input.fns.get(&x)?.name.clone())
 		.filter(|x| binds.binds.get(x).and_then(|x| x.get_addr()).is_some())
@@ -108,11 +112,10 @@ fn find_symbols(pair: &ExecPair, binds: &mut BindDB, symbol: String, candidates:
 	let candidates: Vec<_> = candidates.iter()
 		.filter_map(|x| pair.output.fns.get(x))
 		.map(|x| (x.address.function_addr, x.blocks.iter()
-			.map(|x| x.calls.clone())
-			.flatten()
+			.flat_map(|x| x.calls.clone())
 			.filter_map(|x| match x {
 				Dest::Known(x) => Some(x),
-				Dest::Unknown => None
+				_ => None
 			})
 			.filter_map(|x| binds_reversed.get(&x))
 			.map(|x| x.to_string())
@@ -130,19 +133,16 @@ fn find_symbols(pair: &ExecPair, binds: &mut BindDB, symbol: String, candidates:
 
 	if candidates.len() <= threshold {
 		for candidate in candidates {
-			if binds_reversed_ver.get(&candidate).is_some() {
+			if binds_reversed_ver.contains_key(&candidate) {
 				continue;
 			}
 
-			if conflict_confirm(&symbol, candidate) == Some(true) {
-				binds.binds.insert(symbol.clone(), Bind::Verified(candidate));
+			if conflict_confirm(pair, binds, &symbol, candidate) == Some(true) {
+				binds.set(&symbol, Bind::Verified(candidate), "find");
 				binds_reversed_ver.insert(candidate, symbol.clone());
 				return;
 			} else {
-				match binds.binds.get_mut(&symbol) {
-					Some(Bind::Not(x)) => x.push(candidate),
-					_ => { binds.binds.insert(symbol.clone(), Bind::Not(vec![candidate])); }
-				}
+				reject(binds, &symbol, candidate);
 			}
 		}
 
@@ -155,10 +155,10 @@ fn find_symbols(pair: &ExecPair, binds: &mut BindDB, symbol: String, candidates:
 }
 
 pub fn find_symbol(pair: &ExecPair, binds: &mut BindDB, symbol: String) {
-	return find_symbols(pair, binds, symbol, &pair.output.fns);
+	find_symbols(pair, binds, symbol, &pair.output.fns)
 }
 
-pub fn find_range(pair: &ExecPair, binds: &mut BindDB, cls: String, range_begin: u64, range_end: u64, outfile: &Path) {
+pub fn find_range(pair: &ExecPair, binds: &mut BindDB, cls: String, range_begin: u64, range_end: u64) {
 	let candidates = pair.output.fns.clone().into_iter()
 		.filter(|(_, x)| x.address.function_addr >= range_begin && x.address.function_addr <= range_end)
 		.collect::<HashMap<_, _>>();
@@ -170,6 +170,8 @@ pub fn find_range(pair: &ExecPair, binds: &mut BindDB, cls: String, range_begin:
 
 	for symbol in symbols {
 		find_symbols(pair, binds, symbol, &candidates);
-		std::fs::write(outfile, serde_json::to_string_pretty(&binds).unwrap()).unwrap();
+		binds.checkpoint();
 	}
+
+	binds.save();
 }
@@ -0,0 +1,145 @@
+use std::fmt::Display;
+use std::path::Path;
+
+use crate::db::*;
+
+// Files start with an 8 byte magic and a little endian u32 version, followed by the payload
+const EXDB_MAGIC: &[u8; 8] = b"SYMBOEXD";
+const SYMDB_MAGIC: &[u8; 8] = b"SYMBOSYM";
+const HEADER_LEN: usize = 12;
+
+pub const EXDB_VERSION: u32 = 7;
+// 1 added the version field, 2 added the pair identity and base, 3 added bind sources
+pub const SYMDB_VERSION: u32 = 3;
+
+#[derive(Debug)]
+pub enum FormatError {
+	Io(std::io::Error),
+	TooNew { kind: &'static str, found: u32 },
+	Corrupt { kind: &'static str, version: u32, reason: String }
+}
+
+impl Display for FormatError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			FormatError::Io(e) => write!(f, "{}", e),
+			FormatError::TooNew { kind, found } => write!(f,
+				"{} version {} was written by a newer symbo, this build reads up to version {}",
+				kind, found, if *kind == "exdb" { EXDB_VERSION } else { SYMDB_VERSION }
+			),
+			FormatError::Corrupt { kind, version, reason } => write!(f, "invalid {} (version {}): {}", kind, version, reason)
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum SymFormat {
+	#[default]
+	Json,
+	Binary
+}
+
+impl SymFormat {
+	/// `.bsymdb` files are binary, anything else is JSON
+	pub fn from_path(path: &Path) -> SymFormat {
+		match path.extension().and_then(|x| x.to_str()) {
+			Some("bsymdb") => SymFormat::Binary,
+			_ => SymFormat::Json
+		}
+	}
+
+	pub fn detect(data: &[u8]) -> SymFormat {
+		if data.starts_with(SYMDB_MAGIC) || data.starts_with(b"Pot") {
+			SymFormat::Binary
+		} else {
+			SymFormat::Json
+		}
+	}
+}
+
+fn read_header<'a>(data: &'a [u8], magic: &[u8; 8]) -> (u32, &'a [u8]) {
+	match data.strip_prefix(magic.as_slice()) {
+		Some(rest) if rest.len() >= 4 => (u32::from_le_bytes(rest[..4].try_into().unwrap()), &data[HEADER_LEN..]),
+		// Files from before headers existed are version 0
+		_ => (0, data)
+	}
+}
+
+fn with_header(magic: &[u8; 8], version: u32, payload: Vec<u8>) -> Vec<u8> {
+	let mut out = Vec::with_capacity(HEADER_LEN + payload.len());
+	out.extend_from_slice(magic);
+	out.extend_from_slice(&version.to_le_bytes());
+	out.extend(payload);
+	out
+}
+
+fn corrupt(kind: &'static str, version: u32) -> impl Fn(String) -> FormatError {
+	move |reason| FormatError::Corrupt { kind, version, reason }
+}
+
+/// Returns the version an exdb was written with, without decoding it
+pub fn exdb_version(data: &[u8]) -> u32 {
+	read_header(data, EXDB_MAGIC).0
+}
+
+pub fn decode_exdb(data: &[u8]) -> Result<ExecDB, FormatError> {
+	let (version, payload) = read_header(data, EXDB_MAGIC);
+
+	match version {
+		// 0 is the bare pot encoding, 1 only added the header, 2 added metadata which defaults to empty,
+		// 3 added switch and tail call branches and 4 virtual and import calls, which older files simply don't contain,
+		// 5 added the import table, 6 the globals and 7 block constants, all default to empty
+		0..=7 => pot::from_slice(payload).map_err(|e| corrupt("exdb", version)(e.to_string())),
+		_ => Err(FormatError::TooNew { kind: "exdb", found: version })
+	}
+}
+
+pub fn encode_exdb(exec: &ExecDB) -> Vec<u8> {
+	with_header(EXDB_MAGIC, EXDB_VERSION, pot::to_vec(exec).unwrap())
+}
+
+pub fn symdb_version(data: &[u8]) -> u32 {
+	match SymFormat::detect(data) {
+		SymFormat::Binary => read_header(data, SYMDB_MAGIC).0,
+		SymFormat::Json => serde_json::from_slice::<serde_json::Value>(data).ok()
+			.and_then(|x| x.get("version")?.as_u64())
+			.unwrap_or(0) as u32
+	}
+}
+
+pub fn decode_symdb(data: &[u8]) -> Result<(BindDB, SymFormat), FormatError> {
+	let format = SymFormat::detect(data);
+	let version = symdb_version(data);
+
+	if version > SYMDB_VERSION {
+		return Err(FormatError::TooNew { kind: "symdb", found: version });
+	}
+
+	let db = match format {
+		SymFormat::Json => serde_json::from_slice(data).map_err(|e| corrupt("symdb", version)(e.to_string()))?,
+		SymFormat::Binary => pot::from_slice(read_header(data, SYMDB_MAGIC).1).map_err(|e| corrupt("symdb", version)(e.to_string()))?
+	};
+
+	Ok((db, format))
+}
+
+pub fn encode_symdb(db: &BindDB) -> Vec<u8> {
+	match db.format {
+		SymFormat::Json => serde_json::to_vec_pretty(db).unwrap(),
+		SymFormat::Binary => with_header(SYMDB_MAGIC, SYMDB_VERSION, pot::to_vec(db).unwrap())
+	}
+}
+
+impl ExecDB {
+	pub fn read(path: &Path) -> Result<ExecDB, FormatError> {
+		decode_exdb(&std::fs::read(path).map_err(FormatError::Io)?)
+	}
+
+	pub fn load(path: &Path) -> ExecDB {
+		ExecDB::read(path).unwrap_or_else(|e| panic!("Unable to load {}: {}", path.display(), e))
+	}
+
+	pub fn save(&self, path: &Path) {
+		write_atomic(path, &encode_exdb(self));
+	}
+}
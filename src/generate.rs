@@ -1,41 +1,132 @@
 use std::io::Write;
-use std::collections::{HashMap, BTreeMap};
+use std::collections::{HashMap, HashSet, BTreeMap};
+use std::path::Path;
 
-use crate::pipes::PipeExt;
+use crate::pipes::{Rizin, Recorder};
 use crate::db::*;
 use crate::util::{Warn, AsSome, hex_to_u64};
+use crate::arch::{Arch, get_branch_type, split_instruction, WideConstants};
 
 use serde_json::Value;
 use rzpipe::{RzPipe, RzPipeSpawnOptions};
 
-fn get_branch_type(inst: &str, jump: u64, fail: u64) -> Branch {
-	let mut iter = inst.split(" ");
-	let opcode = iter.next().unwrap();
-
-	if opcode == "ret" {
-		Branch::Return
-	} else {
-		//let operand = iter.next().expect(&format!("Bad instruction: {} at address {}", inst, fail));
-		let fail = Dest::Known(fail);
-		let jump = if jump == 0 {
-			Dest::Unknown
-		} else {
-			Dest::Known(jump)
-		};
+/// Base register and offset of a memory operand like `qword [rax + 0x18]` or `[x8, 0x18]`
+fn mem_operand(text: &str) -> Option<(String, u64)> {
+	let inner = text.split_once('[')?.1.split_once(']')?.0.replace([' ', '#'], "");
+	let mut parts = inner.split(['+', ',']);
+
+	let base = parts.next()?.to_string();
+	if base.contains("rip") || base == "pc" {
+		return None;
+	}
+
+	let offset = match parts.next() {
+		Some(x) => match x.strip_prefix("0x") {
+			Some(x) => u64::from_str_radix(x, 16).ok()?,
+			None => x.parse().ok()?
+		},
+		None => 0
+	};
+
+	(base, offset).as_some()
+}
+
+/// Name of the import behind a `sym.imp.` stub or `reloc.` GOT entry operand
+fn import_name(operand: &str) -> Option<String> {
+	["sym.imp.", "reloc."].iter()
+		.find_map(|x| operand.split_once(x))
+		.map(|(_, x)| x.split([']', '+', ' ']).next().unwrap_or(x).to_string())
+		.filter(|x| !x.is_empty())
+}
+
+// What call operands are resolved against
+struct CallContext<'a> {
+	arch: Arch,
+	label_map: &'a HashMap<String, u64>,
+	// Address points of known vtables
+	vtables: &'a HashMap<u64, String>,
+	// PLT stub address to import name
+	stubs: &'a HashMap<u64, String>,
+	ptr_size: u64
+}
+
+// Follows registers loaded from an object's vtable so indirect calls can be named by slot
+#[derive(Default)]
+struct VirtualTracker {
+	// Register holding a vtable, with its class when it came from a known vtable address
+	vtables: HashMap<String, Option<String>>,
+	// Register holding the method pointer from a vtable slot
+	methods: HashMap<String, (Option<String>, u64)>
+}
+
+impl VirtualTracker {
+	/// Feeds one instruction through, returning the destination if it was a call
+	fn step(&mut self, opcode: &str, operands: &str, ctx: &CallContext) -> Option<Dest> {
+		if ctx.arch.is_call(opcode) {
+			let dest = self.call_dest(operands, ctx);
+			// Calls clobber everything we were following
+			self.vtables.clear();
+			self.methods.clear();
+			return Some(dest);
+		}
+
+		let (reg, source) = operands.split_once(',')?;
+		let (reg, source) = (reg.trim().to_string(), source.trim());
+
+		let known_vtable = source.split(|x: char| !x.is_ascii_alphanumeric())
+			.filter_map(|x| u64::from_str_radix(x.strip_prefix("0x")?, 16).ok())
+			.find_map(|x| ctx.vtables.get(&x));
+		let loaded = matches!(opcode, "mov" | "ldr").then(|| mem_operand(source)).flatten();
+
+		let vtable = known_vtable.map(|x| Some(x.clone()));
+		let method = loaded.as_ref().and_then(|(x, y)| Some((self.vtables.get(x)?.clone(), y / ctx.ptr_size)));
+		// Loading the first field of an object gives its vtable
+		let object_vtable = loaded.filter(|x| x.1 == 0 && method.is_none()).map(|_| None);
+
+		self.vtables.remove(&reg);
+		self.methods.remove(&reg);
 
-		match opcode {
-			"ret" => Branch::Return,
-			"ble" | "blt" | "bls" | "jb" | "jl" | "jle" | "jbe" => Branch::Inequality(fail, jump),
-			"bge" | "bgt" | "bhi" | "ja" | "jg" | "jge" | "jae" => Branch::Inequality(jump, fail),
-			"beq" | "bpl" | "je" | "jz" | "jp" => Branch::Equality(jump, fail),
-			"bne" | "bmi" | "jne" | "jnz" | "jnp" => Branch::Equality(fail, jump),
-			"b" | "br" | "bx" | "bxr" | "jmp" => Branch::Neutral(jump),
-			_ => Branch::Neutral(fail)
+		if let Some(x) = vtable.or(object_vtable) {
+			self.vtables.insert(reg, x);
+		} else if let Some(x) = method {
+			self.methods.insert(reg, x);
 		}
+
+		None
+	}
+
+	fn call_dest(&self, operands: &str, ctx: &CallContext) -> Dest {
+		let compact: String = operands.split_whitespace().collect();
+
+		// Stubs are functions too, but the import name is what survives between versions
+		if let Some(x) = import_name(&compact) {
+			return Dest::Import(x);
+		}
+		let target = ctx.label_map.get(&compact).copied()
+			.or_else(|| u64::from_str_radix(compact.strip_prefix("0x")?, 16).ok());
+		if let Some(x) = target.and_then(|x| ctx.stubs.get(&x)) {
+			return Dest::Import(x.clone());
+		}
+		if let Some(x) = ctx.label_map.get(&compact) {
+			return Dest::Known(*x);
+		}
+		if let Some(class) = mem_operand(operands).and_then(|(x, y)| Some((self.vtables.get(&x)?.clone(), y / ctx.ptr_size))) {
+			return Dest::Virtual(class.0, class.1);
+		}
+		if let Some((class, slot)) = self.methods.get(&compact) {
+			return Dest::Virtual(class.clone(), *slot);
+		}
+
+		Dest::Unknown
 	}
 }
 
-fn nearest_block(val: u64, possible: &Vec<u64>) -> Option<u64> {
+/// The block containing `val`, given sorted block starts
+pub fn nearest_block(val: u64, possible: &[u64]) -> Option<u64> {
+	if possible.is_empty() {
+		return None;
+	}
+
 	let mut low = 0;
 	let mut high = possible.len() - 1;
 
@@ -61,7 +152,8 @@ fn nearest_block(val: u64, possible: &Vec<u64>) -> Option<u64> {
 }
 
 
-pub fn generate(rizin_proj: impl ToString) -> Result<ExecDB, Box<dyn std::error::Error>> {
+/// Runs rizin on a project, saving everything it said to `record` if given
+pub fn generate(rizin_proj: impl ToString, record: Option<&Path>) -> Result<ExecDB, Box<dyn std::error::Error>> {
 	println!("Initializing");
 
 	let mut pipe = RzPipe::spawn("-M", 	Some(RzPipeSpawnOptions {
@@ -69,23 +161,72 @@ pub fn generate(rizin_proj: impl ToString) -> Result<ExecDB, Box<dyn std::error:
 	    args: vec!["-p".to_string(), rizin_proj.to_string()]
 	}))?;
 
+	match record {
+		Some(x) => {
+			let mut pipe = Recorder::new(pipe);
+			let exec = generate_from(&mut pipe)?;
+			pipe.recording.save(x);
+			Ok(exec)
+		},
+		None => generate_from(&mut pipe)
+	}
+}
+
+pub fn generate_from(pipe: &mut impl Rizin) -> Result<ExecDB, Box<dyn std::error::Error>> {
+	println!("Loading Metadata");
+
+	let info = pipe.cmdj("ij")?;
+	let hashes = pipe.cmdj("itj")?;
+	let bin = info.get("bin").cloned().unwrap_or_default();
+	let text = |x: &Value, key: &str| x.get(key).and_then(|x| x.as_str()).map(|x| x.to_string());
+
+	let meta = Metadata {
+		path: info.get("core").and_then(|x| text(x, "file")).unwrap_or_default(),
+		hash: text(&hashes, "sha1").or_else(|| text(&hashes, "md5")).unwrap_or_default(),
+		arch: text(&bin, "arch").unwrap_or_default(),
+		bits: bin.get("bits").and_then(|x| x.as_u64()).unwrap_or(0),
+		base: bin.get("baddr").and_then(|x| x.as_u64()).unwrap_or(0),
+		format: text(&bin, "bintype").unwrap_or_default(),
+		build_id: text(&bin, "guid").filter(|x| !x.is_empty())
+	};
+
 	let label_map: HashMap<String, u64> = pipe.cmd("aflq")?
 		.lines()
 		.map(|x| x.split_whitespace())
 		.filter_map(|mut x| (hex_to_u64(x.next()?)?, x.collect()).as_some())
 		.map(|(x, y)| (y, x))
 		.collect();
-	let function_addrs: Vec<u64> = label_map.values().map(|x| *x).collect();
+	// Sorted so the same binary always gives the same commands and the same exdb
+	let mut function_addrs: Vec<u64> = label_map.values().copied().collect();
+	function_addrs.sort();
 
-	let block_pool: BTreeMap<u64, u64> = pipe.cmd_bulk("afbj @@. {}", &function_addrs)?
+	let blocks_info: Vec<(Value, u64)> = pipe.cmd_bulk("afbj @@. {}", &function_addrs)?
 		.lines()
 		.zip(&function_addrs)
 		.filter_map(|(x, y)| (serde_json::from_str::<Vec<Value>>(x).ok()?, y).as_some())
-		.map(|(x, y)| x.into_iter()
-			.filter_map(|x| x.get("addr").and_then(|x| x.as_u64()))
-			.map(|x| (x, *y))
-		).flatten().collect();
-	let block_keys: Vec<_> = block_pool.keys().map(|x| *x).collect();
+		.flat_map(|(x, y)| x.into_iter().map(|x| (x, *y)))
+		.collect();
+
+	let block_pool: BTreeMap<u64, u64> = blocks_info.iter()
+		.filter_map(|(x, y)| (x.get("addr")?.as_u64()?, *y).as_some())
+		.collect();
+	let block_keys: Vec<_> = block_pool.keys().copied().collect();
+
+	// Jump table targets of every block ending in a switch, in case order
+	let switches: HashMap<u64, Vec<u64>> = blocks_info.iter()
+		.filter_map(|(x, _)| (
+			x.get("addr")?.as_u64()?,
+			x.get("switch_op")?.get("cases")?.as_array()?
+		).as_some())
+		.map(|(x, y)| {
+			let mut cases: Vec<_> = y.iter()
+				.filter_map(|x| (x.get("value").and_then(|x| x.as_u64()).unwrap_or(0), x.get("jump")?.as_u64()?).as_some())
+				.collect();
+			cases.sort();
+			(x, cases.into_iter().map(|x| x.1).collect())
+		})
+		.collect();
+	let function_starts: HashSet<u64> = function_addrs.iter().copied().collect();
 
 	println!("Blocks: {}", block_keys.len());
 
@@ -94,17 +235,32 @@ pub fn generate(rizin_proj: impl ToString) -> Result<ExecDB, Box<dyn std::error:
 	let symbols: HashMap<u64, String> = pipe.cmd("isq~Z")?
 		.lines()
 		.map(|x| x.split_whitespace())
-		.filter_map(|mut x| (hex_to_u64(x.next()?)?, x.skip(1).next()?.to_string()).as_some())
+		.filter_map(|mut x| (hex_to_u64(x.next()?)?, x.nth(1)?.to_string()).as_some())
 		.collect();
 
+	println!("Loading Imports");
+
+	let imports_raw: Vec<(String, u64)> = pipe.cmdj("iij")?
+		.as_array()
+		.map(|x| x.iter()
+			.filter_map(|x| (x.get("name")?.as_str()?.to_string(), x.get("plt").and_then(|x| x.as_u64()).unwrap_or(0)).as_some())
+			.collect())
+		.unwrap_or_default();
+	let stubs: HashMap<u64, String> = imports_raw.iter()
+		.filter(|x| x.1 != 0)
+		.map(|(x, y)| (*y, x.clone()))
+		.collect();
+
+	println!("Imports: {}", imports_raw.len());
+
 	println!("Loading Vtables");
 
 	let vtables_raw: Vec<(u64, Vec<u64>)> = pipe.cmdj("avj")?
 		.as_array().unwrap()
-		.into_iter()
+		.iter()
 		.map(|x| (
 			x.get("offset").unwrap().as_u64().unwrap(),
-			x.get("methods").unwrap().as_array().unwrap().into_iter()
+			x.get("methods").unwrap().as_array().unwrap().iter()
 				.map(|x| nearest_block(x.get("offset").unwrap().as_u64().unwrap(), &block_keys).unwrap())
 				.collect()
 		)).collect();
@@ -127,6 +283,14 @@ pub fn generate(rizin_proj: impl ToString) -> Result<ExecDB, Box<dyn std::error:
 			function_addrs: y.1
 		}).map(|x| (x.name.to_string(), x)).collect();
 
+	let arch = meta.arch();
+
+	// Code loads the address point, but accept the start of the vtable too
+	let ptr_size = meta.ptr_size();
+	let vtable_points: HashMap<u64, String> = vtables.values()
+		.flat_map(|x| [(x.address, x.name.clone()), (x.start(ptr_size), x.name.clone())])
+		.collect();
+
 	println!("Loading Xrefs");
 
 	let xrefs_raw: Vec<(u64, Vec<u64>)> = pipe.cmd_bulk("axtj @@. {}", &function_addrs)?
@@ -172,49 +336,122 @@ pub fn generate(rizin_proj: impl ToString) -> Result<ExecDB, Box<dyn std::error:
 	let mut blocks: HashMap<u64, Block> = pipe.cmd_bulk("pi -1 @@. {}", &block_ends)?
 		.lines()
 		.zip(blocks_raw)
-		.map(|(instr, (addr, size, jump))| (addr, Block {
-			address: Address {
-				addr: addr,
-				block_addr: addr,
-				function_addr: *block_pool.get(&addr).unwrap()
-			},
-			branch: get_branch_type(&instr, jump, addr + size),
-			calls: Vec::new(),
-			strings: Vec::new()
-		})).collect();
+		.map(|(instr, (addr, size, jump))| {
+			let function_addr = *block_pool.get(&addr).unwrap();
+
+			let branch = match (switches.get(&addr), get_branch_type(arch, instr, jump, addr + size)) {
+				(Some(x), _) => Branch::Switch(x.iter().map(|x| Dest::Known(*x)).collect()),
+				(None, Branch::Neutral(Dest::Known(x))) if stubs.contains_key(&x) => Branch::TailCall(Dest::Import(stubs[&x].clone())),
+				// Jumping to the start of some other function rather than falling into it
+				(None, Branch::Neutral(Dest::Known(x))) if x != addr + size && x != function_addr && function_starts.contains(&x) => {
+					Branch::TailCall(Dest::Known(x))
+				},
+				(None, x) => x
+			};
+
+			(addr, Block {
+				address: Address {
+					addr,
+					block_addr: addr,
+					function_addr
+				},
+				branch,
+				calls: Vec::new(),
+				strings: Vec::new(),
+				constants: Vec::new()
+			})
+		}).collect();
 
 	print!("Finding Calls");
 
 	//TODO: replace with function_addrs.len()
 	let len = function_addrs.len();
-	let call_pool: Vec<(u64, Dest)> = (0..len).step_by(100).filter_map(|x| {
+	let code = (block_keys.first().copied().unwrap_or(0), block_ends.iter().max().copied().unwrap_or(0));
+	// Block, call made, constants used and a partial constant completed by every instruction
+	let call_pool: Vec<_> = (0..len).step_by(100).filter_map(|x| {
 		print!("\rFinding Calls {} / {}", x / 100, len / 100);
 		std::io::stdout().flush().unwrap();
 
 		let upper = std::cmp::min(x + 100, len);
 		let batch = &function_addrs[x..upper];
 
-		Some(pipe.cmd_bulk("pDq `afi~size[1]` @@= `cat {}`", &batch).warn_if("Call find failed!").ok()?)
-	}).map(|x|
+		pipe.cmd_bulk("pDq `afi~size[1]` @@= `cat {}`", batch).warn_if("Call find failed!").ok()
+	}).flat_map(|x| {
+		// Every instruction goes through the tracker, only calls come out
+		let mut tracker = VirtualTracker::default();
+		let mut wide = WideConstants::default();
+		let mut current = None;
+		let ctx = CallContext {
+			arch,
+			label_map: &label_map,
+			vtables: &vtable_points,
+			stubs: &stubs,
+			ptr_size
+		};
 		x.lines()
-			.filter(|x| x.contains(" call ") || x.contains(" bl ") || x.contains(" blr ") || x.contains(" blx "))
-			.map(|x| x.split_whitespace().map(|x| x.to_string()))
-			.filter_map(|mut x| (hex_to_u64(&x.next()?)?, x.skip(1).collect::<String>()).as_some())
-			.filter_map(|(x, y)| (
-				nearest_block(x, &block_keys)?,
-				label_map.get(&y).map(|x| Dest::Known(*x)).unwrap_or(Dest::Unknown)
-			).as_some()).collect::<Vec<_>>()
-	).flatten().collect();
+			.filter_map(|x| {
+				let (addr, inst) = x.trim().split_once(' ')?;
+				let addr = u64::from_str_radix(addr.strip_prefix("0x")?, 16).ok()?;
+				let (opcode, operands) = split_instruction(inst);
+				let block = nearest_block(addr, &block_keys)?;
+
+				// Registers don't carry over into another block, let alone another function
+				if current.replace(block) != Some(block) {
+					tracker = VirtualTracker::default();
+					wide = WideConstants::default();
+				}
+
+				(
+					block,
+					tracker.step(opcode, &operands, &ctx),
+					wide.step(arch, opcode, &operands, code)
+				).as_some()
+			}).collect::<Vec<_>>()
+	}).collect();
 
 	println!("\rFinding Calls {} / {}", len / 100, len / 100);
 	println!("Loading Calls");
 
-	call_pool.into_iter().for_each(|(x, y)| {
-		blocks
-			.get_mut(&x)
-			.warn_if(format!("Block not found: {}", x))
-			.map(|x| x.calls.push(y));
+	call_pool.into_iter()
+		.filter(|x| x.1.is_some() || !x.2.0.is_empty() || x.2.1.is_some())
+		.for_each(|(x, y, (z, partial))| {
+			if let Some(x) = blocks.get_mut(&x).warn_if(format!("Block not found: {}", x)) {
+				x.calls.extend(y);
+				x.constants.retain(|x| Some(*x) != partial);
+				for z in z {
+					if !x.constants.contains(&z) {
+						x.constants.push(z);
+					}
+				}
+			}
+		});
+	// Every block calling an import, whether through its stub or straight through the GOT
+	let mut imports: HashMap<String, Import> = imports_raw.into_iter()
+		.map(|(x, y)| (x.clone(), Import {
+			name: x,
+			plt: y,
+			xrefs: Vec::new()
+		}))
+		.collect();
+	blocks.values().for_each(|x| {
+		let tail = match &x.branch {
+			Branch::TailCall(x) => Some(x),
+			_ => None
+		};
+
+		for dest in x.calls.iter().chain(tail) {
+			if let Dest::Import(name) = dest {
+				imports.entry(name.clone())
+					.or_insert_with(|| Import {
+						name: name.clone(),
+						plt: 0,
+						xrefs: Vec::new()
+					})
+					.xrefs.push(x.address);
+			}
+		}
 	});
+
 	println!("Loading Strings");
 
 	let strings_raw: Vec<(u64, String)> = pipe.cmd("izq")?
@@ -242,7 +479,7 @@ pub fn generate(rizin_proj: impl ToString) -> Result<ExecDB, Box<dyn std::error:
 					function_addr: block_pool.get(&y).cloned()?
 				})).collect()
 		})
-		.filter(|x| x.xrefs.len() > 0)
+		.filter(|x| !x.xrefs.is_empty())
 		.fold(HashMap::<String, StringRef>::new(), |mut h, r| {
 			if let Some(x) = h.get_mut(&r.string) {
 				x.xrefs.extend(r.xrefs);
@@ -254,10 +491,46 @@ pub fn generate(rizin_proj: impl ToString) -> Result<ExecDB, Box<dyn std::error:
 
 	strings.values().for_each(|x|
 		x.xrefs.iter().for_each(|y| {
-			blocks.get_mut(&y.block_addr).map(|y| y.strings.push(x.string.to_string()));
+			if let Some(y) = blocks.get_mut(&y.block_addr) {
+				y.strings.push(x.string.to_string());
+			}
 		})
 	);
 
+	println!("Loading Globals");
+
+	let skip: HashSet<u64> = string_addrs.iter().chain(&function_addrs).copied().collect();
+	let mut globals: HashMap<u64, Global> = HashMap::new();
+	pipe.cmd_bulk("axffj @@. {}", &function_addrs)?
+		.lines()
+		.filter_map(|x| serde_json::from_str::<Vec<Value>>(x).ok())
+		.flatten()
+		.filter(|x| x.get("type").and_then(|x| x.as_str()) == Some("DATA"))
+		.filter_map(|x| (
+			x.get("at").or(x.get("from")).and_then(|x| x.as_u64())?,
+			x.get("ref").or(x.get("to")).and_then(|x| x.as_u64())?,
+			text(&x, "name").or_else(|| text(&x, "refname"))
+		).as_some())
+		// Strings have their own table and code refs are function pointers, not data
+		.filter(|x| !skip.contains(&x.1))
+		.filter(|x| !x.2.as_deref().is_some_and(|x| x.starts_with("str.") || x.starts_with("reloc.")))
+		.filter_map(|(x, y, z)| (x, nearest_block(x, &block_keys)?, y, z).as_some())
+		.filter_map(|(x, y, z, w)| (x, y, *block_pool.get(&y).warn_if(format!("No function owns block {:#x}", y))?, z, w).as_some())
+		.for_each(|(addr, blk, func, target, name)| {
+			let global = globals.entry(target).or_insert_with(|| Global {
+				name: name.map(|x| x.strip_prefix("obj.").or(x.strip_prefix("sym.")).map(|x| x.to_string()).unwrap_or(x)),
+				address: target,
+				xrefs: Vec::new()
+			});
+			global.xrefs.push(Address {
+				addr,
+				block_addr: blk,
+				function_addr: func
+			});
+		});
+
+	println!("Globals: {}", globals.len());
+
 	println!("Loading Functions");
 
 	let mut functions: HashMap<u64, Function> = function_addrs.into_iter()
@@ -269,18 +542,38 @@ pub fn generate(rizin_proj: impl ToString) -> Result<ExecDB, Box<dyn std::error:
 				function_addr: x
 			},
 			blocks: Vec::new(),
-			xrefs: xrefs.get(&x).cloned().unwrap_or_else(|| Vec::new())
+			xrefs: xrefs.get(&x).cloned().unwrap_or_else(Vec::new)
 		})).collect();
 
+	// Tail calls show up as code xrefs rather than calls, so add their callers here
+	blocks.values()
+		.filter_map(|x| match x.branch {
+			Branch::TailCall(Dest::Known(y)) => Some((y, x.address)),
+			_ => None
+		})
+		.for_each(|(x, y)| {
+			if let Some(x) = functions.get_mut(&x) {
+				x.xrefs.push(y);
+			}
+		});
+
 	blocks.drain().for_each(|(_, x)| {
 		functions.get_mut(&x.address.function_addr).unwrap().blocks.push(x);
 	});
+	functions.values_mut().for_each(|x| {
+		x.blocks.sort_by_key(|x| x.address.block_addr);
+		x.xrefs.sort_by_key(|x| x.addr);
+	});
+	imports.values_mut().for_each(|x| x.xrefs.sort_by_key(|x| x.addr));
 
 	println!("Done");
 
 	Ok(ExecDB {
+		meta,
 		fns: functions,
-		vtables: vtables,
-		strings: strings
+		vtables,
+		strings,
+		imports,
+		globals
 	})
 }
\ No newline at end of file
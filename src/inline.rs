@@ -0,0 +1,197 @@
+use std::collections::{HashMap, HashSet, BTreeMap};
+use colored::Colorize;
+
+use crate::util::*;
+use crate::db::*;
+use crate::analysis::{align, block_traverse, bound_at, call_score};
+
+pub struct InlineCandidate {
+	pub symbol: String,
+	pub evidence: Vec<String>
+}
+
+fn entry_block(func: &Function) -> Option<&Block> {
+	func.blocks.iter().find(|x| x.address.block_addr == x.address.function_addr)
+}
+
+fn known_calls(func: &Function) -> impl Iterator<Item = u64> + '_ {
+	all_calls(func).filter_map(|x| match x {
+		Dest::Known(x) => Some(*x),
+		_ => None
+	})
+}
+
+fn all_calls(func: &Function) -> impl Iterator<Item = &Dest> {
+	func.blocks.iter().flat_map(|x| x.calls.iter().chain(match &x.branch {
+		Branch::TailCall(x) => Some(x),
+		_ => None
+	}))
+}
+
+fn all_strings(func: &Function) -> HashSet<&String> {
+	func.blocks.iter().flat_map(|x| &x.strings).collect()
+}
+
+/// Input functions whose call sites vanished from their bound callers, along with why we think so
+pub fn detect(pair: &ExecPair, binds: &BindDB) -> Vec<InlineCandidate> {
+	let (in_base, out_base) = (pair.input.meta.base, pair.output.meta.base);
+	let bound_out: HashSet<u64> = binds.binds.values().filter_map(|x| x.get_addr()).collect();
+	let bound = bound_at(binds);
+	let bound_addr = |x: u64| pair.input.fns.get(&x)
+		.and_then(|x| x.name.as_ref())
+		.and_then(|x| binds.binds.get(x))
+		.and_then(|x| x.get_addr());
+
+	let mut fns: Vec<_> = pair.input.fns.values().collect();
+	fns.sort_by_key(|x| x.address.function_addr);
+
+	let mut candidates = Vec::new();
+
+	for func in fns {
+		let Some(name) = &func.name else {
+			continue;
+		};
+		if matches!(binds.binds.get(name), Some(Bind::Verified(_) | Bind::Unverified(_) | Bind::Inline)) {
+			continue;
+		}
+
+		let strings = all_strings(func);
+		// Output address of each matched callee, with its name
+		let callees: HashMap<u64, &String> = known_calls(func)
+			.filter_map(|x| (bound_addr(x)?, pair.input.fns.get(&x)?.name.as_ref()?).as_some())
+			.collect();
+
+		let mut sites: BTreeMap<u64, Vec<&Address>> = BTreeMap::new();
+		func.xrefs.iter().for_each(|x| sites.entry(x.function_addr).or_default().push(x));
+
+		let mut evidence = Vec::new();
+		let mut callers = 0;
+		let mut content = 0;
+		let mut vanished_everywhere = true;
+
+		for (caller_addr, sites) in sites {
+			let Some(caller) = pair.input.fns.get(&caller_addr) else {
+				continue;
+			};
+			let Some(out_fn) = bound_addr(caller_addr).and_then(|x| pair.output.fns.get(&x)) else {
+				continue;
+			};
+			let caller_name = caller.name.as_deref().map(demangle).unwrap_or_default();
+			callers += 1;
+
+			let aligned = entry_block(caller).zip(entry_block(out_fn))
+				.map(|x| block_traverse(binds, pair, vec![x]))
+				.unwrap_or_default();
+
+			// Prefer the aligned call site, it says exactly where the call went missing
+			let mut vanished = false;
+			let mut site_aligned = false;
+			for site in sites {
+				let Some(in_blk) = pair.input.addr_to_block(site) else {
+					continue;
+				};
+				if let Some((_, out_blk)) = aligned.iter().find(|(x, _)| x.address == in_blk.address) {
+					site_aligned = true;
+					// The call to this function has to be one the output block has nothing lined up with
+					let matched: HashSet<usize> = align(&in_blk.calls, &out_blk.calls, |x, y| call_score(binds, pair, &bound, x, y))
+						.into_iter()
+						.map(|x| x.0)
+						.collect();
+					let missing = in_blk.calls.iter().enumerate()
+						.any(|(i, x)| !matched.contains(&i) && *x == Dest::Known(func.address.function_addr));
+
+					if missing {
+						vanished = true;
+						evidence.push(format!("call at {} in {} has no counterpart in aligned block {}",
+							site.addr.as_addr(in_base), caller_name, out_blk.address.block_addr.as_addr(out_base)));
+					}
+				}
+			}
+
+			// Otherwise compare how many calls go to functions nobody has matched yet
+			if !site_aligned {
+				let unmatched_in = all_calls(caller)
+					.filter(|x| match x {
+						Dest::Known(x) => bound_addr(*x).is_none(),
+						Dest::Import(_) => false,
+						_ => true
+					})
+					.count();
+				let unmatched_out = all_calls(out_fn)
+					.filter(|x| match x {
+						Dest::Known(x) => !bound_out.contains(x),
+						Dest::Import(_) => false,
+						_ => true
+					})
+					.count();
+
+				if unmatched_out < unmatched_in {
+					vanished = true;
+					evidence.push(format!("{} makes {} calls to unmatched functions, its match makes {}", caller_name, unmatched_in, unmatched_out));
+				}
+			}
+
+			if !vanished {
+				vanished_everywhere = false;
+				break;
+			}
+
+			let caller_strings = all_strings(caller);
+			let moved_strings: Vec<_> = all_strings(out_fn).into_iter()
+				.filter(|x| strings.contains(x) && !caller_strings.contains(x))
+				.map(|x| format!("{:?}", x))
+				.collect();
+
+			let caller_callees: HashSet<u64> = known_calls(caller).filter_map(bound_addr).collect();
+			let out_callees: HashSet<u64> = known_calls(out_fn).collect();
+			let moved_calls: Vec<_> = callees.iter()
+				.filter(|(x, _)| out_callees.contains(x) && !caller_callees.contains(x))
+				.map(|(_, x)| demangle(x))
+				.collect();
+
+			if !moved_strings.is_empty() {
+				content += moved_strings.len();
+				evidence.push(format!("strings {} show up in {}", moved_strings.join(", "), caller_name));
+			}
+			if !moved_calls.is_empty() {
+				content += moved_calls.len();
+				evidence.push(format!("calls to {} show up in {}", moved_calls.join(", "), caller_name));
+			}
+		}
+
+		if callers == 0 || !vanished_everywhere {
+			continue;
+		}
+
+		// Leaf functions leave nothing behind, so a missing call in one caller isn't enough for them
+		let leaf = strings.is_empty() && callees.is_empty();
+		if content == 0 && !(leaf && callers > 1) {
+			continue;
+		}
+
+		candidates.push(InlineCandidate {
+			symbol: name.clone(),
+			evidence
+		});
+	}
+
+	candidates
+}
+
+/// Asks about each candidate, marking confirmed ones as inline
+pub fn process(binds: &mut BindDB, candidates: &[InlineCandidate]) -> usize {
+	println!("Found {} possible inlines", candidates.len().to_string().bright_green());
+
+	let mut count = 0;
+	for candidate in candidates {
+		if inline_confirm(binds, &candidate.symbol, &candidate.evidence) == Some(true) {
+			binds.set(&candidate.symbol, Bind::Inline, "inline");
+			count += 1;
+		}
+		binds.checkpoint();
+	}
+
+	binds.save();
+	println!("Marked {} symbols as inline", count.to_string().bright_green());
+	count
+}
@@ -0,0 +1,157 @@
+use std::io::Write;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use colored::Colorize;
+
+use crate::util::*;
+use crate::db::*;
+
+/// Milliseconds since the epoch, used for both timestamps and run ids
+pub fn now() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map(|x| x.as_millis() as u64).unwrap_or(0)
+}
+
+pub fn journal_path(symdb: &Path) -> PathBuf {
+	let mut path = symdb.as_os_str().to_owned();
+	path.push(".journal");
+	PathBuf::from(path)
+}
+
+pub fn append(symdb: &Path, entries: &[JournalEntry]) {
+	if entries.is_empty() {
+		return;
+	}
+
+	let mut file = std::fs::OpenOptions::new()
+		.create(true)
+		.append(true)
+		.open(journal_path(symdb))
+		.expect("Unable to open journal");
+
+	let lines: String = entries.iter()
+		.map(|x| serde_json::to_string(x).unwrap() + "\n")
+		.collect();
+
+	file.write_all(lines.as_bytes()).expect("Unable to write journal");
+}
+
+/// Folds each symbol's changes within a run into one entry, dropping runs that left a symbol as they found it.
+/// Rejections are kept as they are, check reads them back from the journal
+pub fn compact(entries: Vec<JournalEntry>) -> Vec<JournalEntry> {
+	let mut merged: Vec<JournalEntry> = Vec::new();
+	let mut index: HashMap<(u64, String), usize> = HashMap::new();
+
+	for entry in entries {
+		match index.get(&(entry.run, entry.symbol.clone())) {
+			Some(&i) if !matches!(merged[i].new, Some(Bind::Not(_))) => {
+				let first = std::mem::replace(&mut merged[i], entry);
+				merged[i].old = first.old;
+			},
+			_ => {
+				index.insert((entry.run, entry.symbol.clone()), merged.len());
+				merged.push(entry);
+			}
+		}
+	}
+
+	merged.into_iter().filter(|x| x.old != x.new).collect()
+}
+
+/// Replaces the journal with `entries`
+pub fn rewrite(symdb: &Path, entries: &[JournalEntry]) {
+	let lines: String = entries.iter()
+		.map(|x| serde_json::to_string(x).unwrap() + "\n")
+		.collect();
+
+	write_atomic(&journal_path(symdb), lines.as_bytes());
+}
+
+pub fn read(symdb: &Path) -> Vec<JournalEntry> {
+	std::fs::read_to_string(journal_path(symdb))
+		.map(|x| x.lines()
+			.filter_map(|x| serde_json::from_str(x).warn_if("Skipping bad journal entry").ok())
+			.collect())
+		.unwrap_or_default()
+}
+
+fn age(time: u64) -> String {
+	let secs = now().saturating_sub(time) / 1000;
+
+	match secs {
+		0..=59 => format!("{}s ago", secs),
+		60..=3599 => format!("{}m ago", secs / 60),
+		3600..=86399 => format!("{}h ago", secs / 3600),
+		_ => format!("{}d ago", secs / 86400)
+	}
+}
+
+fn bind_str(bind: &Option<Bind>, base: u64) -> String {
+	bind.as_ref().map(|x| x.describe(base)).unwrap_or("-".to_string())
+}
+
+pub fn print_history(entries: &[JournalEntry], symbol: Option<&str>, base: u64) {
+	let mut last_run = 0;
+
+	for (i, entry) in entries.iter().enumerate() {
+		if symbol.is_some_and(|x| x != entry.symbol) {
+			continue;
+		}
+
+		if entry.run != last_run {
+			println!("{} {} ({})", "Run".bold(), entry.run.to_string().bold(), age(entry.run));
+			last_run = entry.run;
+		}
+
+		println!("  {:>6} {:<14} {} {} {} {}",
+			i.to_string().dimmed(),
+			entry.source.cyan(),
+			demangle(&entry.symbol).yellow(),
+			bind_str(&entry.old, base).red(),
+			"->".dimmed(),
+			bind_str(&entry.new, base).green()
+		);
+	}
+}
+
+/// The newest run that still has a change in effect, so repeated undos keep stepping back
+pub fn last_run(binds: &BindDB, entries: &[JournalEntry]) -> Option<u64> {
+	let mut runs: Vec<u64> = entries.iter().filter(|x| x.source != "undo").map(|x| x.run).collect();
+	runs.dedup();
+
+	runs.into_iter().rev().find(|run| {
+		// Only the last change to each symbol in the run says what the run left behind
+		let mut last: HashMap<&String, &JournalEntry> = HashMap::new();
+		entries.iter()
+			.filter(|x| x.run == *run && x.source != "undo" && x.source != "rebase")
+			.for_each(|x| {
+				last.insert(&x.symbol, x);
+			});
+		last.values().any(|x| binds.binds.get(&x.symbol) == x.new.as_ref())
+	})
+}
+
+/// Reverts the given entries, newest first. Entries whose symbol has changed since are skipped.
+pub fn undo(binds: &mut BindDB, entries: &[&JournalEntry]) -> usize {
+	let mut count = 0;
+
+	// The symdb base isn't journaled, so undoing a rebase would leave it pointing at the wrong image
+	if entries.iter().any(|x| x.source == "rebase") {
+		println!("Skipping rebased entries, run {} again instead", "symbo rebase".bold());
+	}
+
+	for entry in entries.iter().rev().filter(|x| x.source != "rebase") {
+		if binds.binds.get(&entry.symbol) != entry.new.as_ref() {
+			println!("Skipping {}, it was changed again later", demangle(&entry.symbol).yellow());
+			continue;
+		}
+
+		match &entry.old {
+			Some(x) => binds.set(&entry.symbol, x.clone(), "undo"),
+			None => binds.remove(&entry.symbol, "undo")
+		}
+		count += 1;
+	}
+
+	count
+}
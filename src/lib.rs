@@ -0,0 +1,19 @@
+pub mod pipes;
+pub mod db;
+pub mod generate;
+pub mod util;
+pub mod analysis;
+pub mod find;
+pub mod diff;
+pub mod journal;
+pub mod format;
+pub mod chain;
+pub mod project;
+pub mod export;
+pub mod report;
+pub mod check;
+pub mod strip;
+pub mod merge;
+pub mod dbdiff;
+pub mod inline;
+pub mod arch;
@@ -1,16 +1,15 @@
 use colored::Colorize;
-use std::path::PathBuf;
-use std::fs;
+use std::path::{Path, PathBuf};
 
-mod pipes;
-mod db;
-mod generate;
-mod util;
-mod analysis;
-mod find;
+use symbo::{generate, util, analysis, find, diff, journal, chain, export, report, check, strip, merge, dbdiff, inline};
 
-use crate::db::*;
-use crate::util::hex_to_u64;
+use symbo::db::*;
+use symbo::format::*;
+use symbo::util::{hex_to_u64, parse_addr, AsHex, AsSome, Warn};
+use symbo::project::{Project, PROJECT_FILE};
+use symbo::export::{ExportFormat, ExportTarget};
+use symbo::report::ReportFormat;
+use symbo::strip::BindState;
 
 use clap::{Parser, Subcommand};
 
@@ -18,123 +17,597 @@ use clap::{Parser, Subcommand};
 #[command(name = "Symbo")]
 struct Cli {
     #[command(subcommand)]
-    command: Command
+    command: Command,
+    /// Show and accept addresses relative to the image base
+    #[clap(long, global = true)]
+    rva: bool
 }
 
 #[derive(Subcommand)]
 enum Command {
+    /// Create a symbo.toml in the current directory
+    Init,
+    /// Generate an exdb from a rizin project, or both project binaries when omitted
     Generate {
-        exec: PathBuf,
+        exec: Option<PathBuf>,
 
         #[clap(short, long)]
-        output: Option<PathBuf>
+        output: Option<PathBuf>,
+        /// Save every rizin command and its output, for replaying without rizin
+        #[clap(long)]
+        record: Option<PathBuf>
     },
     Run {
-        from: PathBuf,
-        to: PathBuf,
+        from: Option<PathBuf>,
+        to: Option<PathBuf>,
         #[clap(short, long)]
-        out: Option<PathBuf>
+        out: Option<PathBuf>,
+        /// Continue even if the symdb was built against different binaries
+        #[clap(long)]
+        force: bool
     },
+    /// Propagate symbols down an ordered list of exdbs, one symdb per hop
+    Chain {
+        /// Defaults to the project's chain
+        exdbs: Vec<PathBuf>,
+        /// Directory for the per-hop symdbs
+        #[clap(short, long, default_value = ".")]
+        dir: PathBuf,
+        /// List the symbols that were not matched at each hop
+        #[clap(long)]
+        lost: bool,
+        /// Continue even if a symdb was built against different binaries
+        #[clap(long)]
+        force: bool
+    },
+    /// Write a copy of an output exdb with function names taken from a symdb
+    Apply {
+        /// Defaults to the project's output exdb
+        exec: Option<PathBuf>,
+        /// Defaults to the project's symdb
+        symdb: Option<PathBuf>,
+        /// Defaults to <exec>.named.exdb
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+        /// Only apply verified binds
+        #[clap(long)]
+        verified: bool
+    },
+    /// Dump a function, the exdb is always given since either side of a project could be meant
     Print {
         exec: PathBuf,
         addr: u64
     },
-    /// Remove unverified symbols from symdb
+    /// Write bound symbols to the project's export targets, or to a single file
+    Export {
+        file: Option<PathBuf>,
+        #[clap(short, long, requires = "format")]
+        output: Option<PathBuf>,
+        #[clap(short, long)]
+        format: Option<ExportFormat>,
+        #[clap(long)]
+        verified: bool
+    },
+    /// Summarize a symdb's coverage of the input binary
+    Report {
+        from: Option<PathBuf>,
+        to: Option<PathBuf>,
+        #[clap(short, long)]
+        out: Option<PathBuf>,
+        #[clap(short, long, value_enum, default_value = "text")]
+        format: ReportFormat,
+        /// Write the report to a file instead of stdout
+        #[clap(short, long)]
+        write: Option<PathBuf>,
+        /// How many classes, namespaces and unmatched functions to list
+        #[clap(long, default_value_t = 20)]
+        top: usize
+    },
+    /// Validate a symdb against the exdbs, exiting non-zero on problems
+    Check {
+        from: Option<PathBuf>,
+        to: Option<PathBuf>,
+        #[clap(short, long)]
+        out: Option<PathBuf>
+    },
+    /// Combine symdbs, verified binds win and conflicting ones are reported
+    Merge {
+        /// A single file is merged into the project's symdb
+        #[clap(required = true, num_args = 1..)]
+        files: Vec<PathBuf>,
+        /// Defaults to merging into the first file
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+        /// Merge even if the symdbs were built against different binaries
+        #[clap(long)]
+        force: bool
+    },
+    /// List binds that differ between two symdbs
+    DiffDb {
+        old: PathBuf,
+        /// Defaults to the project's symdb
+        new: Option<PathBuf>
+    },
+    /// Show which binary an exdb was generated from
+    Info {
+        exec: PathBuf
+    },
+    /// Remove symbols from symdb, by default every unverified one
     Strip {
-        file: PathBuf
+        file: Option<PathBuf>,
+        /// Bind states to remove, defaults to unverified
+        #[clap(long, value_enum)]
+        state: Vec<BindState>,
+        /// Only binds last set by these strategies or commands
+        #[clap(long)]
+        source: Vec<String>,
+        /// Only binds within [START, END)
+        #[clap(long, num_args = 2, value_names = ["START", "END"])]
+        range: Vec<String>,
+        /// Glob over demangled names, e.g. 'cocos2d::CCNode::*'
+        #[clap(short, long)]
+        pattern: Option<String>,
+        /// Only binds with a confidence below this, verified binds are 1.0
+        #[clap(long)]
+        below: Option<f64>,
+        /// List what would be removed without changing anything
+        #[clap(long)]
+        dry_run: bool,
+        /// Put back everything removed by earlier strips
+        #[clap(long)]
+        restore: bool
+    },
+    /// Look for functions inlined into their callers and offer to mark them
+    Inline {
+        from: Option<PathBuf>,
+        to: Option<PathBuf>,
+        #[clap(short, long)]
+        out: Option<PathBuf>,
+        /// Continue even if the symdb was built against different binaries
+        #[clap(long)]
+        force: bool
     },
     /// Attempt to find specific symbol
     Find {
-        from: PathBuf,
-        to: PathBuf,
+        from: Option<PathBuf>,
+        to: Option<PathBuf>,
         #[clap(short, long)]
         symbol: String,
         #[clap(short, long)]
-        out: PathBuf
+        out: Option<PathBuf>,
+        /// Continue even if the symdb was built against different binaries
+        #[clap(long)]
+        force: bool
     },
     /// Find symbols from class within range
+    #[command(override_usage = "symbo range [OPTIONS] --class <CLASS> [FROM TO] <START> <END>")]
     Range {
-        from: PathBuf,
-        to: PathBuf,
-        start: String,
-        end: String,
+        /// [FROM TO] START END, the exdbs default to the project's
+        #[clap(required = true, num_args = 2..=4, value_name = "ARGS")]
+        args: Vec<String>,
         #[clap(short, long)]
         class: String,
         #[clap(short, long)]
-        out: PathBuf
+        out: Option<PathBuf>,
+        /// Continue even if the symdb was built against different binaries
+        #[clap(long)]
+        force: bool
+    },
+    /// Rewrite a symdb as JSON or binary
+    Convert {
+        input: PathBuf,
+        output: PathBuf,
+        /// Defaults to binary for .bsymdb outputs and JSON otherwise
+        #[clap(short, long)]
+        format: Option<SymFormat>
+    },
+    /// Move an exdb or symdb to a different image base, 0x0 stores RVAs
+    Rebase {
+        file: PathBuf,
+        base: String,
+        /// Base a symdb's addresses were taken at, for symdbs that don't record one
+        #[clap(long)]
+        from_base: Option<String>
+    },
+    /// Upgrade exdb and symdb files to the current format in place
+    Migrate {
+        files: Vec<PathBuf>
+    },
+    /// List recorded changes to a symdb
+    History {
+        file: Option<PathBuf>,
+        #[clap(short, long)]
+        symbol: Option<String>
+    },
+    /// Roll back journal entries, defaulting to the most recent run
+    Undo {
+        file: Option<PathBuf>,
+        /// Journal entry indices to revert
+        #[clap(short, long)]
+        entry: Vec<usize>,
+        /// Run id to revert
+        #[clap(short, long)]
+        run: Option<u64>
+    },
+    /// Show input and output function side by side
+    #[command(override_usage = "symbo diff [OPTIONS] [FROM TO] <SYMBOL>")]
+    Diff {
+        /// [FROM TO] SYMBOL, the exdbs default to the project's
+        #[clap(required = true, num_args = 1..=3, value_name = "ARGS")]
+        args: Vec<String>,
+        /// Output address to compare against, defaults to the bound address
+        #[clap(short, long)]
+        addr: Option<String>,
+        #[clap(short, long)]
+        out: Option<PathBuf>
+    }
+}
+
+fn load_pair(project: &Project, from: Option<PathBuf>, to: Option<PathBuf>) -> ExecPair {
+    let (from, to) = project.pair_paths(from, to);
+
+    ExecPair {
+        input: ExecDB::load(&from),
+        output: ExecDB::load(&to)
+    }
+}
+
+/// Splits off the exdb paths from commands whose positionals are `[FROM TO] ARGS...`
+fn split_paths(mut args: Vec<String>, count: usize) -> (Option<PathBuf>, Option<PathBuf>, Vec<String>) {
+    match args.len() - count {
+        0 => (None, None, args),
+        2 => {
+            let rest = args.split_off(2);
+            let to = args.pop().map(PathBuf::from);
+            (args.pop().map(PathBuf::from), to, rest)
+        },
+        _ => panic!("Give both FROM and TO or neither")
     }
 }
 
 fn main() {
 
     let args = Cli::parse();
+    let project = Project::load();
+    util::show_rva(args.rva);
+    util::set_conflict_policy(project.conflicts);
 
     match args.command {
-        Command::Generate { exec, output } => {
-            let out_file = output.unwrap_or_else(|| PathBuf::from((exec.file_name().unwrap().to_string_lossy() + ".exdb").to_string()));
-            fs::write(&out_file, "").expect("Unable to write to output file!");
+        Command::Init => {
+            Project::init(Path::new(PROJECT_FILE));
+            println!("Created {}", PROJECT_FILE.bright_green());
+        },
 
-            let out_data = generate::generate(exec.display().to_string()).unwrap();
-            fs::write(&out_file, pot::to_vec(&out_data).unwrap()).unwrap();
+        Command::Generate { exec, output, record } => {
+            let jobs = match exec {
+                Some(exec) => vec![(
+                    output.unwrap_or_else(|| PathBuf::from((exec.file_name().unwrap().to_string_lossy() + ".exdb").to_string())),
+                    exec
+                )],
+                None => [&project.input, &project.output].into_iter()
+                    .flatten()
+                    .filter_map(|x| (x.exdb.clone(), x.project.clone()?).as_some())
+                    .collect()
+            };
+
+            if jobs.is_empty() {
+                panic!("No rizin project given and none declared in {}", PROJECT_FILE);
+            }
+            if record.is_some() && jobs.len() > 1 {
+                panic!("--record needs a single rizin project");
+            }
 
+            for (out_file, exec) in jobs {
+                let out_data = generate::generate(exec.display().to_string(), record.as_deref()).unwrap();
+                out_data.save(&out_file);
+            }
         },
         
-        Command::Run { from, to, out } => {
-            let pair = ExecPair {
-                input: pot::from_slice(&std::fs::read(from).unwrap()).expect("Invalid exdb file"),
-                output: pot::from_slice(&std::fs::read(to).unwrap()).expect("Invalid exdb file")
+        Command::Run { from, to, out, force } => {
+            let pair = load_pair(&project, from, to);
+
+            let file_path = project.symdb(out);
+
+            let mut binds = BindDB::open(&file_path, &pair);
+            binds.check_pair(&pair, force);
+
+            println!("To do!");
+
+            analysis::run_strategies(&pair, &mut binds, &project.strategies);
+        },
+
+        Command::Chain { exdbs, dir, lost, force } => {
+            let exdbs = if exdbs.is_empty() { project.chain.clone() } else { exdbs };
+            chain::run_chain(&exdbs, &dir, &project.strategies, force, lost);
+        },
+
+        Command::Apply { exec, symdb, output, verified } => {
+            let exec = exec.or_else(|| project.output.as_ref().map(|x| x.exdb.clone()))
+                .unwrap_or_else(|| panic!("No exdb given and no [output] in {}", PROJECT_FILE));
+            let symdb = project.symdb(symdb);
+            let out_file = output.unwrap_or_else(|| exec.with_extension("named.exdb"));
+            let mut exec = ExecDB::load(&exec);
+            let binds = BindDB::load(&symdb);
+
+            if let Some(base) = binds.base.filter(|x| *x != exec.meta.base) {
+                panic!("symdb is based at {} but the exdb is at {}, rebase one of them first", base.as_hex(), exec.meta.base.as_hex());
+            }
+
+            let count = exec.apply_names(&binds, verified);
+            exec.save(&out_file);
+
+            println!("Named {} functions in {}", count.to_string().bright_green(), out_file.display());
+        },
+
+        Command::Strip { file, state, source, range, pattern, below, dry_run, restore } => {
+            let file = project.symdb(file);
+            let side = strip::stripped_path(&file);
+            let mut binds = BindDB::load(&file);
+
+            if restore {
+                let count = strip::restore(&mut binds, &side);
+                println!("Restored {} symbols", count.to_string().bright_green());
+                binds.save();
+                return;
+            }
+
+            let base = binds.base.unwrap_or(0);
+            let filter = strip::StripFilter {
+                states: state,
+                sources: source,
+                range: (range.len() == 2).then(|| (
+                    parse_addr(&range[0], base).expect("Invalid range start"),
+                    parse_addr(&range[1], base).expect("Invalid range end")
+                )),
+                pattern,
+                below
             };
 
-            let file_path = out.unwrap_or(PathBuf::from("symbols.symdb"));
+            let count = strip::strip(&mut binds, &filter, &side, dry_run);
 
-            let mut binds = if file_path.exists() {
-                serde_json::from_slice(&std::fs::read(&file_path).unwrap()).expect("Invalid symdb file")
+            if dry_run {
+                println!("Would remove {} symbols", count.to_string().yellow());
+            } else {
+                println!("Removed {} symbols, restore them with --restore", count.to_string().bright_green());
+                binds.save();
+            }
+        },
+
+        Command::Inline { from, to, out, force } => {
+            let pair = load_pair(&project, from, to);
+
+            let mut binds = BindDB::load(&project.symdb(out));
+            binds.check_pair(&pair, force);
+            let candidates = inline::detect(&pair, &binds);
+            inline::process(&mut binds, &candidates);
+        },
+
+        Command::Find { from, to, symbol, out, force } => {
+            let pair = load_pair(&project, from, to);
+
+            let mut binds = BindDB::load(&project.symdb(out));
+            binds.check_pair(&pair, force);
+            find::find_symbol(&pair, &mut binds, symbol);
+            binds.save();
+
+        },
+
+        Command::Range { args, class, out, force } => {
+            let (from, to, args) = split_paths(args, 2);
+            let (start, end) = (&args[0], &args[1]);
+            let pair = load_pair(&project, from, to);
+
+            let mut binds = BindDB::load(&project.symdb(out));
+            binds.check_pair(&pair, force);
+            let base = pair.output.meta.base;
+            find::find_range(&pair, &mut binds, class, parse_addr(start, base).unwrap(), parse_addr(end, base).unwrap());
+        },
+
+        Command::Convert { input, output, format } => {
+            let mut binds = BindDB::load(&input);
+
+            binds.format = format.unwrap_or_else(|| SymFormat::from_path(&output));
+            binds.attach(&output);
+            binds.save();
+
+            println!("Wrote {} symbols as {:?}", binds.binds.len().to_string().bright_green(), binds.format);
+        },
+
+        Command::Rebase { file, base, from_base } => {
+            let base = hex_to_u64(&base).expect("Invalid base");
+            let from_base = from_base.map(|x| hex_to_u64(&x).expect("Invalid base"));
+            let data = std::fs::read(&file).expect("Unable to read file");
+
+            match decode_exdb(&data) {
+                Ok(mut exec) => {
+                    let old = exec.meta.base;
+                    exec.rebase(base);
+                    exec.save(&file);
+                    println!("Rebased exdb from {} to {}", old.as_hex(), base.as_hex().bright_green());
+                },
+                Err(e @ FormatError::TooNew { .. }) => panic!("Unable to rebase {}: {}", file.display(), e),
+                Err(_) => {
+                    let mut binds = BindDB::load(&file);
+                    match (binds.base, from_base) {
+                        (Some(x), Some(y)) if x != y => panic!("symdb is based at {}, not {}", x.as_hex(), y.as_hex()),
+                        (Some(_), _) => (),
+                        (None, Some(y)) => binds.base = Some(y),
+                        (None, None) => panic!("{} has no recorded image base, pass the base its addresses were taken at with --from-base", file.display())
+                    }
+                    let old = binds.base.unwrap();
+                    binds.rebase(base);
+                    binds.save();
+                    println!("Rebased symdb from {} to {}", old.as_hex(), base.as_hex().bright_green());
+                }
+            }
+        },
+
+        Command::Migrate { files } => {
+            for file in files {
+                let data = std::fs::read(&file).expect("Unable to read file");
+
+                match decode_exdb(&data) {
+                    Ok(exec) => {
+                        exec.save(&file);
+                        println!("{}: exdb version {} -> {}", file.display(), exdb_version(&data), EXDB_VERSION.to_string().bright_green());
+                    },
+                    Err(e @ FormatError::TooNew { .. }) => panic!("Unable to migrate {}: {}", file.display(), e),
+                    Err(_) => {
+                        let mut binds = BindDB::load(&file);
+                        binds.save();
+                        println!("{}: symdb version {} -> {}", file.display(), symdb_version(&data), SYMDB_VERSION.to_string().bright_green());
+                    }
+                }
+            }
+        },
+
+        Command::History { file, symbol } => {
+            let file = project.symdb(file);
+            let base = BindDB::load(&file).base.unwrap_or(0);
+            journal::print_history(&journal::read(&file), symbol.as_deref(), base);
+        },
+
+        Command::Undo { file, entry, run } => {
+            let file = project.symdb(file);
+            let mut binds = BindDB::load(&file);
+            let entries = journal::read(&file);
+
+            let selected: Vec<_> = if !entry.is_empty() {
+                // Journal order, so they are reverted newest first whatever order they were given in
+                let mut entry = entry;
+                entry.sort();
+                entry.dedup();
+                entry.iter().filter_map(|x| entries.get(*x).warn_if(format!("No journal entry {}", x))).collect()
+            } else {
+                let run = run
+                    .or_else(|| journal::last_run(&binds, &entries))
+                    .expect("Nothing to undo");
+                entries.iter().filter(|x| x.run == run).collect()
+            };
+
+            println!("Reverted {} changes", journal::undo(&mut binds, &selected).to_string().bright_green());
+            binds.save();
+        },
+
+        Command::Diff { args, addr, out } => {
+            let (from, to, mut args) = split_paths(args, 1);
+            let symbol = args.pop().unwrap();
+            let pair = load_pair(&project, from, to);
+
+            let file_path = project.symdb(out);
+            let binds = if file_path.exists() {
+                BindDB::load(&file_path)
             } else {
                 BindDB::new(&pair)
             };
 
-            println!("To do!");
+            let addr = addr.and_then(|x| parse_addr(&x, pair.output.meta.base))
+                .or_else(|| binds.binds.get(&symbol).and_then(|x| x.get_addr()))
+                .expect("No address given and symbol is not bound");
 
-            binds.process(analysis::string_xref_strat(&pair, &binds), &file_path);
-            binds.process(analysis::block_traverse_strat(&pair, &binds), &file_path);
-            binds.process(analysis::call_xref_strat(&pair, &binds), &file_path);
-            binds.process(analysis::call_block_strat(&pair, &binds), &file_path);
+            diff::print_fn_diff(&pair, &binds, &symbol, addr);
         },
 
-        Command::Strip { file } => {
-            let mut binds: BindDB = serde_json::from_slice(&std::fs::read(&file).unwrap()).expect("Invalid symdb file");
-            let before_count = binds.binds.len();
+        Command::Export { file, output, format, verified } => {
+            let binds = BindDB::load(&project.symdb(file));
 
-            binds.binds.retain(|_, x| !matches!(x, Bind::Unverified(_)));
+            let targets = match (output, format) {
+                (Some(path), Some(format)) => vec![ExportTarget { path, format, rva: util::showing_rva(), verified_only: verified }],
+                _ => project.export.clone()
+            };
 
-            println!("Removed {} symbols", (before_count - binds.binds.len()).to_string().bright_green());
+            if targets.is_empty() {
+                panic!("No export target given and none declared in {}", PROJECT_FILE);
+            }
 
-            std::fs::write(file, serde_json::to_string_pretty(&binds).unwrap()).unwrap();
+            for target in targets {
+                let count = export::export(&binds, &target);
+                println!("Exported {} symbols to {}", count.to_string().bright_green(), target.path.display());
+            }
         },
 
-        Command::Find { from, to, symbol, out } => {
-            let pair = ExecPair {
-                input: pot::from_slice(&std::fs::read(from).unwrap()).expect("Invalid exdb file"),
-                output: pot::from_slice(&std::fs::read(to).unwrap()).expect("Invalid exdb file")
+        Command::Report { from, to, out, format, write, top } => {
+            let pair = load_pair(&project, from, to);
+            let binds = BindDB::load(&project.symdb(out));
+            let report = report::build(&pair, &binds, top);
+
+            let text = match format {
+                ReportFormat::Text => {
+                    report.print_text(top, pair.input.meta.base);
+                    None
+                },
+                ReportFormat::Json => Some(serde_json::to_string_pretty(&report).unwrap()),
+                ReportFormat::Html => Some(report.to_html(top, pair.input.meta.base))
             };
 
-            let mut binds: BindDB = serde_json::from_slice(&std::fs::read(&out).unwrap()).expect("Invalid symdb file");
-            find::find_symbol(&pair, &mut binds, symbol);
+            match (text, write) {
+                (Some(text), Some(path)) => write_atomic(&path, text.as_bytes()),
+                (Some(text), None) => println!("{}", text),
+                (None, Some(_)) => println!("{}", "Text reports are only printed, use --format json or html to write a file".yellow()),
+                (None, None) => ()
+            }
+        },
 
+        Command::Check { from, to, out } => {
+            let pair = load_pair(&project, from, to);
+            let file = project.symdb(out);
+            let binds = BindDB::load(&file);
+
+            let issues = check::check(&pair, &binds, &check::rejections(&journal::read(&file)));
+            check::print_issues(&issues);
+
+            if !issues.is_empty() {
+                std::process::exit(1);
+            }
         },
 
-        Command::Range { from, to, start, end, class, out } => {
-            let pair = ExecPair {
-                input: pot::from_slice(&std::fs::read(from).unwrap()).expect("Invalid exdb file"),
-                output: pot::from_slice(&std::fs::read(to).unwrap()).expect("Invalid exdb file")
-            };
+        Command::Merge { mut files, output, force } => {
+            if files.len() == 1 {
+                files.insert(0, project.symdb(None));
+            }
+
+            let mut binds = BindDB::load(&files[0]);
+            if let Some(output) = &output {
+                binds.attach(output);
+            }
+
+            let mut conflicts = 0;
+            for file in &files[1..] {
+                println!("{} {}", "Merging".bold(), file.display());
+                let other = BindDB::load(file);
+                merge::check_compatible(&mut binds, &other, force);
+
+                let stats = merge::merge(&mut binds, &other);
+                merge::print_stats(&stats, binds.base.unwrap_or(0));
+                conflicts += stats.conflicts.len();
+            }
+
+            binds.save();
+            println!("Wrote {} symbols", binds.binds.len().to_string().bright_green());
+
+            if conflicts > 0 {
+                std::process::exit(1);
+            }
+        },
+
+        Command::DiffDb { old, new } => {
+            let old = BindDB::load(&old);
+            let new = BindDB::load(&project.symdb(new));
+
+            if old.base.is_some() != new.base.is_some() {
+                println!("{}", "Only one symdb records its image base, addresses are compared as they are. Give the other one with rebase --from-base first if they differ".yellow());
+            }
+
+            dbdiff::print_diff(&dbdiff::diff(&old, &new), old.base.unwrap_or(0));
+        },
 
-            let mut binds: BindDB = serde_json::from_slice(&std::fs::read(&out).unwrap()).expect("Invalid symdb file");
-            find::find_range(&pair, &mut binds, class, hex_to_u64(&start).unwrap(), hex_to_u64(&end).unwrap(), &out);
+        Command::Info { exec } => {
+            println!("{}", ExecDB::load(&exec).meta);
         },
 
         Command::Print { exec, addr } => {
-            let exec: ExecDB = pot::from_slice(&std::fs::read(exec).unwrap()).expect("Invalid exdb file");
+            let exec = ExecDB::load(&exec);
+            let addr = if util::showing_rva() { addr.wrapping_add(exec.meta.base) } else { addr };
             println!("{:#?}", exec.fns.get(&addr));
         }
     }
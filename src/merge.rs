@@ -0,0 +1,149 @@
+use std::collections::{BTreeMap, HashSet};
+use colored::Colorize;
+
+use crate::util::*;
+use crate::db::*;
+
+pub enum Conflict {
+	/// Symbol, the kept bind and the other side's bind
+	Bind(String, Bind, Bind),
+	/// Address verified for more than one symbol
+	Address(u64, Vec<String>)
+}
+
+#[derive(Default)]
+pub struct MergeStats {
+	pub added: usize,
+	pub upgraded: usize,
+	pub unioned: usize,
+	pub duplicates: usize,
+	pub conflicts: Vec<Conflict>
+}
+
+/// Picks the winner of two binds for one symbol, None when they need a human to decide
+fn resolve(ours: &Bind, theirs: &Bind) -> Option<Bind> {
+	match (ours, theirs) {
+		(x, y) if x == y => Some(x.clone()),
+
+		(Bind::Verified(_), Bind::Verified(_)) => None,
+		(Bind::Verified(_), Bind::Inline) | (Bind::Inline, Bind::Verified(_)) => None,
+		(Bind::Verified(x), Bind::Not(n)) | (Bind::Not(n), Bind::Verified(x)) => (!n.contains(x)).then_some(Bind::Verified(*x)),
+		(Bind::Verified(x), Bind::Unverified(_)) | (Bind::Unverified(_), Bind::Verified(x)) => Some(Bind::Verified(*x)),
+
+		// Someone looked at it, which beats a strategy's guess
+		(Bind::Not(x), Bind::Not(y)) => {
+			let mut union = x.clone();
+			union.extend(y.iter().filter(|z| !x.contains(z)));
+			Some(Bind::Not(union))
+		},
+		(Bind::Not(x), Bind::Unverified(_)) | (Bind::Unverified(_), Bind::Not(x)) => Some(Bind::Not(x.clone())),
+		(Bind::Inline, _) | (_, Bind::Inline) => Some(Bind::Inline),
+
+		// Two different guesses, keep ours
+		(Bind::Unverified(x), Bind::Unverified(_)) => Some(Bind::Unverified(*x))
+	}
+}
+
+/// Folds `other` into `binds`, journaling every change
+pub fn merge(binds: &mut BindDB, other: &BindDB) -> MergeStats {
+	let mut stats = MergeStats::default();
+
+	let mut symbols: Vec<_> = other.binds.keys().collect();
+	symbols.sort();
+
+	for symbol in symbols {
+		let theirs = &other.binds[symbol];
+		let source = other.sources.get(symbol).map(|x| x.as_str()).unwrap_or("merge");
+
+		let Some(ours) = binds.binds.get(symbol).cloned() else {
+			stats.added += 1;
+			binds.set(symbol, theirs.clone(), source);
+			continue;
+		};
+
+		match resolve(&ours, theirs) {
+			None => stats.conflicts.push(Conflict::Bind(symbol.clone(), ours, theirs.clone())),
+			Some(x) if x == ours => (),
+			Some(x) if x == *theirs => {
+				stats.upgraded += 1;
+				binds.set(symbol, x, source);
+			},
+			Some(x) => {
+				stats.unioned += 1;
+				binds.set(symbol, x, "merge");
+			}
+		}
+	}
+
+	// Each file can verify a different symbol at the same address, only one of them is right
+	let mut verified: BTreeMap<u64, Vec<String>> = BTreeMap::new();
+	for (symbol, bind) in &binds.binds {
+		if let Bind::Verified(x) = bind {
+			verified.entry(*x).or_default().push(symbol.clone());
+		}
+	}
+	for (addr, symbols) in &mut verified {
+		if symbols.len() > 1 {
+			symbols.sort();
+			stats.conflicts.push(Conflict::Address(*addr, symbols.clone()));
+		}
+	}
+
+	// Guesses at an address someone else verified are stale now
+	let verified: HashSet<_> = verified.into_keys().collect();
+	let stale: Vec<_> = binds.binds.iter()
+		.filter(|(_, x)| matches!(x, Bind::Unverified(y) if verified.contains(y)))
+		.map(|(x, _)| x.clone())
+		.collect();
+
+	for symbol in &stale {
+		binds.remove(symbol, "merge");
+	}
+	stats.duplicates = stale.len();
+
+	binds.checkpoint();
+	stats
+}
+
+/// Refuses symdbs matched against different binaries or bases unless forced
+pub fn check_compatible(binds: &mut BindDB, other: &BindDB, force: bool) {
+	match (binds.base, other.base) {
+		(Some(x), Some(y)) if x != y => panic!("symdbs are based at {} and {}, rebase one of them first", x.as_hex(), y.as_hex()),
+		(None, Some(y)) => binds.base = Some(y),
+		_ => ()
+	}
+
+	match (&binds.identity, &other.identity) {
+		(Some(x), Some(y)) if x != y => {
+			println!("{}", "symdbs were built against different input/output pairs".red());
+			if !force {
+				println!("Use --force to merge anyway");
+				std::process::exit(1);
+			}
+		},
+		(None, Some(y)) => binds.identity = Some(y.clone()),
+		_ => ()
+	}
+}
+
+pub fn print_stats(stats: &MergeStats, base: u64) {
+	println!("Added {} symbols", stats.added.to_string().bright_green());
+	println!("Took {} stronger binds", stats.upgraded.to_string().bright_green());
+	if stats.unioned > 0 {
+		println!("Combined {} not lists", stats.unioned.to_string().bright_green());
+	}
+	if stats.duplicates > 0 {
+		println!("Dropped {} unverified binds at verified addresses", stats.duplicates.to_string().yellow());
+	}
+
+	if !stats.conflicts.is_empty() {
+		println!("{}", format!("{} conflicts need resolving by hand, kept the first file's binds:", stats.conflicts.len()).red());
+		for conflict in &stats.conflicts {
+			match conflict {
+				Conflict::Bind(symbol, ours, theirs) => println!("  {} {} vs {}", demangle(symbol).yellow(), ours.describe(base), theirs.describe(base)),
+				Conflict::Address(addr, symbols) => println!("  {} verified for {}", addr.as_addr(base),
+					symbols.iter().map(|x| demangle(x).yellow().to_string()).collect::<Vec<_>>().join(", "))
+			}
+		}
+	}
+}
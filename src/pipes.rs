@@ -1,15 +1,36 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
 use dynfmt::Format;
 use tempfile::NamedTempFile;
 use dynfmt::SimpleCurlyFormat;
 use rzpipe::{RzPipe, RzPipeError};
+use serde::{Serialize, Deserialize};
 use serde_json::Value;
 
-pub trait PipeExt {
+/// Everything generate asks of rizin, so a recording can stand in for a live session
+pub trait Rizin {
+	fn cmd(&mut self, command: &str) -> Result<String, RzPipeError>;
 	fn cmd_bulk(&mut self, command: &str, offsets: &[u64]) -> Result<String, RzPipeError>;
-	fn cmdj_bulk(&mut self, command: &str, offsets: &[u64]) -> Result<Value, RzPipeError>;
+
+	fn cmdj(&mut self, command: &str) -> Result<Value, RzPipeError> {
+		parse(&self.cmd(command)?)
+	}
+
+	fn cmdj_bulk(&mut self, command: &str, offsets: &[u64]) -> Result<Value, RzPipeError> {
+		parse(&self.cmd_bulk(command, offsets)?)
+	}
 }
 
-impl PipeExt for RzPipe {
+fn parse(text: &str) -> Result<Value, RzPipeError> {
+	serde_json::from_str(text).map_err(|e| RzPipeError::ConcretePipe(e.to_string()))
+}
+
+impl Rizin for RzPipe {
+	fn cmd(&mut self, command: &str) -> Result<String, RzPipeError> {
+		RzPipe::cmd(self, command)
+	}
+
 	fn cmd_bulk(&mut self, command: &str, offsets: &[u64]) -> Result<String, RzPipeError> {
 		let tmp_file = NamedTempFile::new().unwrap();
 
@@ -18,17 +39,81 @@ impl PipeExt for RzPipe {
 			offsets.iter().map(|x| x.to_string()).collect::<Vec<_>>().join("\n")
 		).unwrap();
 
-		self.cmd(&SimpleCurlyFormat.format(command, &[tmp_file.path().to_str().unwrap()]).unwrap())
+		RzPipe::cmd(self, &SimpleCurlyFormat.format(command, [tmp_file.path().to_str().unwrap()]).unwrap())
+	}
+}
+
+/// Bulk commands go through a temporary file, so they're keyed by the offsets instead of its path
+fn key(command: &str, offsets: Option<&[u64]>) -> String {
+	match offsets {
+		Some(x) => format!("{} < {}", command, x.iter().map(|x| format!("{:#x}", x)).collect::<Vec<_>>().join(" ")),
+		None => command.to_string()
 	}
+}
 
-	fn cmdj_bulk(&mut self, command: &str, offsets: &[u64]) -> Result<Value, RzPipeError> {
-		let tmp_file = NamedTempFile::new().unwrap();
+/// Output of every command run during a session
+#[derive(Serialize, Deserialize, Default)]
+pub struct Recording {
+	pub commands: BTreeMap<String, String>
+}
 
-		std::fs::write(
-			tmp_file.path(),
-			offsets.iter().map(|x| x.to_string()).collect::<Vec<_>>().join("\n")
-		).unwrap();
+impl Recording {
+	pub fn load(path: &Path) -> Recording {
+		let data = std::fs::read(path).unwrap_or_else(|e| panic!("Unable to read {}: {}", path.display(), e));
+		serde_json::from_slice(&data).unwrap_or_else(|e| panic!("Unable to load {}: {}", path.display(), e))
+	}
 
-		self.cmdj(&SimpleCurlyFormat.format(command, &[tmp_file.path().to_str().unwrap()]).unwrap())
+	pub fn save(&self, path: &Path) {
+		std::fs::write(path, serde_json::to_string_pretty(self).unwrap()).expect("Unable to write recording");
 	}
-}
\ No newline at end of file
+}
+
+/// Passes commands through to rizin, keeping what comes back
+pub struct Recorder<P: Rizin> {
+	pub pipe: P,
+	pub recording: Recording
+}
+
+impl<P: Rizin> Recorder<P> {
+	pub fn new(pipe: P) -> Recorder<P> {
+		Recorder {
+			pipe,
+			recording: Recording::default()
+		}
+	}
+}
+
+impl<P: Rizin> Rizin for Recorder<P> {
+	fn cmd(&mut self, command: &str) -> Result<String, RzPipeError> {
+		let out = self.pipe.cmd(command)?;
+		self.recording.commands.insert(key(command, None), out.clone());
+		Ok(out)
+	}
+
+	fn cmd_bulk(&mut self, command: &str, offsets: &[u64]) -> Result<String, RzPipeError> {
+		let out = self.pipe.cmd_bulk(command, offsets)?;
+		self.recording.commands.insert(key(command, Some(offsets)), out.clone());
+		Ok(out)
+	}
+}
+
+/// Answers from a recording, no rizin needed
+pub struct Replay(pub Recording);
+
+impl Replay {
+	fn get(&self, key: String) -> Result<String, RzPipeError> {
+		self.0.commands.get(&key)
+			.cloned()
+			.ok_or_else(|| RzPipeError::ConcretePipe(format!("Nothing recorded for {}", key)))
+	}
+}
+
+impl Rizin for Replay {
+	fn cmd(&mut self, command: &str) -> Result<String, RzPipeError> {
+		self.get(key(command, None))
+	}
+
+	fn cmd_bulk(&mut self, command: &str, offsets: &[u64]) -> Result<String, RzPipeError> {
+		self.get(key(command, Some(offsets)))
+	}
+}
@@ -0,0 +1,100 @@
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+
+use crate::util::ConflictPolicy;
+use crate::export::ExportTarget;
+
+pub const PROJECT_FILE: &str = "symbo.toml";
+
+const TEMPLATE: &str = r#"# Symbo project, picked up by every command run from this directory
+
+symdb = "symbols.symdb"
+
+# Strategies used by `run` and `chain`, in order
+strategies = ["string_xref", "import_xref", "global_xref", "block_traverse", "call_xref", "call_block"]
+
+# What to do when a bind conflicts: "ask", "skip" or "reject"
+conflicts = "ask"
+
+# Exdbs for `chain`, oldest first
+chain = []
+
+[input]
+exdb = "input.exdb"
+# Rizin project used by `generate`
+project = "input.rzdb"
+
+[output]
+exdb = "output.exdb"
+project = "output.rzdb"
+
+# Files written by `export`
+[[export]]
+path = "symbols.csv"
+format = "csv"
+rva = false
+"#;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Binary {
+	pub exdb: PathBuf,
+	pub project: Option<PathBuf>
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct Project {
+	pub symdb: PathBuf,
+	pub strategies: Vec<String>,
+	pub conflicts: ConflictPolicy,
+	pub chain: Vec<PathBuf>,
+	pub input: Option<Binary>,
+	pub output: Option<Binary>,
+	pub export: Vec<ExportTarget>
+}
+
+impl Default for Project {
+	fn default() -> Self {
+		Project {
+			symdb: PathBuf::from("symbols.symdb"),
+			strategies: crate::analysis::STRATEGIES.iter().map(|x| x.0.to_string()).collect(),
+			conflicts: ConflictPolicy::Ask,
+			chain: Vec::new(),
+			input: None,
+			output: None,
+			export: Vec::new()
+		}
+	}
+}
+
+impl Project {
+	/// Reads symbo.toml from the current directory, falling back to defaults without one
+	pub fn load() -> Project {
+		match std::fs::read_to_string(PROJECT_FILE) {
+			Ok(x) => toml::from_str(&x).unwrap_or_else(|e| panic!("Invalid {}: {}", PROJECT_FILE, e)),
+			Err(_) => Project::default()
+		}
+	}
+
+	pub fn init(path: &Path) {
+		if path.exists() {
+			panic!("{} already exists", path.display());
+		}
+
+		std::fs::write(path, TEMPLATE).expect("Unable to write project file");
+	}
+
+	/// Exdb paths given on the command line, or the project's input/output
+	pub fn pair_paths(&self, from: Option<PathBuf>, to: Option<PathBuf>) -> (PathBuf, PathBuf) {
+		(
+			from.or_else(|| self.input.as_ref().map(|x| x.exdb.clone()))
+				.unwrap_or_else(|| panic!("No input exdb given and no [input] in {}", PROJECT_FILE)),
+			to.or_else(|| self.output.as_ref().map(|x| x.exdb.clone()))
+				.unwrap_or_else(|| panic!("No output exdb given and no [output] in {}", PROJECT_FILE))
+		)
+	}
+
+	pub fn symdb(&self, file: Option<PathBuf>) -> PathBuf {
+		file.unwrap_or_else(|| self.symdb.clone())
+	}
+}
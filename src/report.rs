@@ -0,0 +1,275 @@
+use std::collections::{HashMap, BTreeMap};
+use serde::Serialize;
+use colored::Colorize;
+
+use crate::util::*;
+use crate::db::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum ReportFormat {
+	Text,
+	Json,
+	Html
+}
+
+#[derive(Serialize, Default, Clone)]
+pub struct Coverage {
+	pub bound: usize,
+	pub total: usize
+}
+
+#[derive(Serialize)]
+pub struct Unmatched {
+	pub symbol: String,
+	pub address: u64,
+	pub blocks: usize
+}
+
+#[derive(Serialize)]
+pub struct Report {
+	pub verified: usize,
+	pub unverified: usize,
+	pub not: usize,
+	pub inline: usize,
+	pub named: Coverage,
+	pub classes: BTreeMap<String, Coverage>,
+	pub namespaces: BTreeMap<String, Coverage>,
+	pub sources: BTreeMap<String, usize>,
+	pub unmatched: Vec<Unmatched>
+}
+
+// Length of the template arguments at the start of `s`, up to and including their closing E
+fn template_len(s: &str) -> Option<usize> {
+	let bytes = s.as_bytes();
+	let mut depth = 0;
+	let mut i = 0;
+
+	while i < bytes.len() {
+		match bytes[i] {
+			// Names inside are length prefixed, and may well contain an E
+			b'0'..=b'9' => {
+				let digits = s[i..].chars().take_while(|x| x.is_ascii_digit()).count();
+				i += digits + s[i..i + digits].parse::<usize>().ok()?;
+				continue;
+			},
+			// Substitutions and template parameters, S_, S1_, T_, T0_ or St, Sa, ...
+			b'S' | b'T' => {
+				let id = s[i + 1..].chars().take_while(|x| x.is_ascii_digit() || x.is_ascii_uppercase()).count();
+				i += if s[i + 1 + id..].starts_with('_') { id + 2 } else { 2 };
+				continue;
+			},
+			// Literals hold plain numbers
+			b'L' => {
+				i += s[i..].find('E')? + 1;
+				continue;
+			},
+			b'I' | b'N' | b'X' | b'J' => depth += 1,
+			b'E' => {
+				depth -= 1;
+				if depth == 0 {
+					return Some(i + 1);
+				}
+			},
+			_ => ()
+		}
+		i += 1;
+	}
+
+	None
+}
+
+/// Splits an Itanium nested name into its scope, `_ZN7cocos2d6CCNode4initEv` gives ["cocos2d", "CCNode"]
+/// and `_ZN2ns3FooIN1a1bEE3barEv` gives ["ns", "Foo<a::b>"]
+pub fn scope_of(symbol: &str) -> Vec<String> {
+	let Some(mut rest) = symbol.strip_prefix("_ZN") else {
+		return Vec::new();
+	};
+	rest = rest.trim_start_matches(['K', 'V', 'r']);
+	let nested = rest;
+
+	let mut parts: Vec<String> = Vec::new();
+	loop {
+		let digits = rest.chars().take_while(|x| x.is_ascii_digit()).count();
+		let Some(len) = rest[..digits].parse::<usize>().ok() else {
+			break;
+		};
+		let Some(part) = rest.get(digits..digits + len) else {
+			break;
+		};
+		rest = &rest[digits + len..];
+
+		// Template arguments belong to the part before them, the demangler spells them out
+		let mut part = part.to_string();
+		if rest.starts_with('I') {
+			let Some(len) = template_len(rest) else {
+				break;
+			};
+			rest = &rest[len..];
+
+			let shown = demangle(&format!("_ZN{}E", &nested[..nested.len() - rest.len()]));
+			let scope = parts.iter().map(|x| format!("{}::", x)).collect::<String>();
+			if let Some(x) = shown.strip_prefix(&scope) {
+				part = x.to_string();
+			}
+		}
+
+		parts.push(part);
+	}
+
+	// The last name is the function's own only when the nested name ends right after it, or an abi tag does.
+	// Constructors, destructors and operators (C1, D2, pl, cv...) aren't length prefixed, so everything is scope
+	if rest.starts_with(['E', 'B']) {
+		parts.pop();
+	}
+
+	parts
+}
+
+fn add(map: &mut BTreeMap<String, Coverage>, key: String, bound: bool) {
+	let entry = map.entry(key).or_default();
+	entry.total += 1;
+	if bound {
+		entry.bound += 1;
+	}
+}
+
+pub fn build(pair: &ExecPair, binds: &BindDB, top: usize) -> Report {
+	let mut report = Report {
+		verified: 0,
+		unverified: 0,
+		not: 0,
+		inline: 0,
+		named: Coverage::default(),
+		classes: BTreeMap::new(),
+		namespaces: BTreeMap::new(),
+		sources: BTreeMap::new(),
+		unmatched: Vec::new()
+	};
+
+	for (symbol, bind) in &binds.binds {
+		match bind {
+			Bind::Verified(_) => report.verified += 1,
+			Bind::Unverified(_) => report.unverified += 1,
+			Bind::Not(_) => report.not += 1,
+			Bind::Inline => report.inline += 1
+		}
+
+		if bind.get_addr().is_some() {
+			let source = binds.sources.get(symbol).cloned().unwrap_or("unknown".to_string());
+			*report.sources.entry(source).or_default() += 1;
+		}
+	}
+
+	let named: HashMap<&String, &Function> = pair.input.fns.values()
+		.filter_map(|x| (x.name.as_ref()?, x).as_some())
+		.collect();
+
+	for (symbol, func) in &named {
+		let bound = binds.binds.get(*symbol).and_then(|x| x.get_addr()).is_some();
+		report.named.total += 1;
+		if bound {
+			report.named.bound += 1;
+		}
+
+		let scope = scope_of(symbol);
+		add(&mut report.namespaces, scope.first().cloned().unwrap_or("(global)".to_string()), bound);
+		if !scope.is_empty() {
+			add(&mut report.classes, scope.join("::"), bound);
+		}
+
+		// Inlined functions have nothing left to find
+		if !bound && binds.binds.get(*symbol) != Some(&Bind::Inline) {
+			report.unmatched.push(Unmatched {
+				symbol: symbol.to_string(),
+				address: func.address.function_addr,
+				blocks: func.blocks.len()
+			});
+		}
+	}
+
+	report.unmatched.sort_by(|x, y| y.blocks.cmp(&x.blocks).then(x.symbol.cmp(&y.symbol)));
+	report.unmatched.truncate(top);
+
+	report
+}
+
+impl Coverage {
+	fn percent(&self) -> f64 {
+		self.bound as f64 * 100.0 / self.total.max(1) as f64
+	}
+}
+
+impl Report {
+	fn largest(map: &BTreeMap<String, Coverage>, top: usize) -> Vec<(&String, &Coverage)> {
+		let mut list: Vec<_> = map.iter().collect();
+		list.sort_by(|x, y| y.1.total.cmp(&x.1.total).then(x.0.cmp(y.0)));
+		list.truncate(top);
+		list
+	}
+
+	pub fn print_text(&self, top: usize, base: u64) {
+		println!("{}", "Binds".bold());
+		println!("  verified   {}", self.verified.to_string().bright_green());
+		println!("  unverified {}", self.unverified.to_string().yellow());
+		println!("  not        {}", self.not.to_string().red());
+		println!("  inline     {}", self.inline);
+		println!("  {}/{} named input functions bound ({:.1}%)", self.named.bound, self.named.total, self.named.percent());
+
+		println!("{}", "Strategies".bold());
+		for (source, count) in &self.sources {
+			println!("  {:<16} {}", source, count);
+		}
+
+		for (title, map) in [("Namespaces", &self.namespaces), ("Classes", &self.classes)] {
+			println!("{}", title.bold());
+			for (name, cov) in Report::largest(map, top) {
+				println!("  {:>6.1}% {:>5}/{:<5} {}", cov.percent(), cov.bound, cov.total, name);
+			}
+		}
+
+		println!("{}", "Largest unmatched".bold());
+		for func in &self.unmatched {
+			println!("  {:>5} blocks {} {}", func.blocks, func.address.as_addr(base).blue(), demangle(&func.symbol).yellow());
+		}
+	}
+
+	pub fn to_html(&self, top: usize, base: u64) -> String {
+		fn escape(x: &str) -> String {
+			x.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+		}
+
+		fn table(title: &str, head: &[&str], rows: Vec<Vec<String>>) -> String {
+			format!("<h2>{}</h2>\n<table>\n<tr>{}</tr>\n{}</table>\n",
+				title,
+				head.iter().map(|x| format!("<th>{}</th>", x)).collect::<String>(),
+				rows.into_iter()
+					.map(|x| format!("<tr>{}</tr>\n", x.iter().map(|x| format!("<td>{}</td>", escape(x))).collect::<String>()))
+					.collect::<String>()
+			)
+		}
+
+		let coverage = |map| Report::largest(map, top).into_iter()
+			.map(|(x, y)| vec![x.clone(), format!("{}/{}", y.bound, y.total), format!("{:.1}%", y.percent())])
+			.collect();
+
+		let mut out = String::from("<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Symbo report</title>\n\
+			<style>body{font-family:sans-serif}table{border-collapse:collapse}td,th{border:1px solid #ccc;padding:2px 8px;text-align:left}</style>\n\
+			</head><body>\n<h1>Symbo report</h1>\n");
+
+		out += &table("Binds", &["State", "Count"], vec![
+			vec!["verified".to_string(), self.verified.to_string()],
+			vec!["unverified".to_string(), self.unverified.to_string()],
+			vec!["not".to_string(), self.not.to_string()],
+			vec!["inline".to_string(), self.inline.to_string()],
+			vec!["named input bound".to_string(), format!("{}/{} ({:.1}%)", self.named.bound, self.named.total, self.named.percent())]
+		]);
+		out += &table("Strategies", &["Source", "Binds"], self.sources.iter().map(|(x, y)| vec![x.clone(), y.to_string()]).collect());
+		out += &table("Namespaces", &["Namespace", "Bound", "Coverage"], coverage(&self.namespaces));
+		out += &table("Classes", &["Class", "Bound", "Coverage"], coverage(&self.classes));
+		out += &table("Largest unmatched", &["Symbol", "Address", "Blocks"], self.unmatched.iter()
+			.map(|x| vec![demangle(&x.symbol), x.address.as_addr(base), x.blocks.to_string()])
+			.collect());
+
+		out + "</body></html>\n"
+	}
+}
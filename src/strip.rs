@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use colored::Colorize;
+
+use crate::util::*;
+use crate::db::*;
+use crate::analysis::confidence;
+
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum BindState {
+	Verified,
+	Unverified,
+	Not,
+	Inline
+}
+
+impl BindState {
+	pub fn of(bind: &Bind) -> BindState {
+		match bind {
+			Bind::Verified(_) => BindState::Verified,
+			Bind::Unverified(_) => BindState::Unverified,
+			Bind::Not(_) => BindState::Not,
+			Bind::Inline => BindState::Inline
+		}
+	}
+}
+
+pub struct StripFilter {
+	/// Defaults to unverified when empty
+	pub states: Vec<BindState>,
+	pub sources: Vec<String>,
+	/// Half open address range
+	pub range: Option<(u64, u64)>,
+	/// Glob over the demangled name, `cocos2d::CCNode::*`
+	pub pattern: Option<String>,
+	/// Only binds with a confidence below this
+	pub below: Option<f64>
+}
+
+#[derive(Serialize, Deserialize)]
+struct Stripped {
+	bind: Bind,
+	source: Option<String>
+}
+
+fn glob(pattern: &str, text: &str) -> bool {
+	match pattern.split_once('*') {
+		None => pattern == text,
+		Some((head, rest)) => {
+			let Some(text) = text.strip_prefix(head) else {
+				return false;
+			};
+			(0..=text.len()).filter(|x| text.is_char_boundary(*x)).any(|x| glob(rest, &text[x..]))
+		}
+	}
+}
+
+impl StripFilter {
+	pub fn matches(&self, symbol: &str, bind: &Bind, source: Option<&str>) -> bool {
+		let states: &[BindState] = if self.states.is_empty() { &[BindState::Unverified] } else { &self.states };
+		if !states.contains(&BindState::of(bind)) {
+			return false;
+		}
+
+		if !self.sources.is_empty() && !source.is_some_and(|x| self.sources.iter().any(|y| y == x)) {
+			return false;
+		}
+
+		if let Some((start, end)) = self.range {
+			if !bind.get_addr().is_some_and(|x| x >= start && x < end) {
+				return false;
+			}
+		}
+
+		if let Some(pattern) = &self.pattern {
+			if !glob(pattern, &demangle(symbol)) {
+				return false;
+			}
+		}
+
+		self.below.map(|x| confidence(bind, source) < x).unwrap_or(true)
+	}
+}
+
+/// Side file that stripped binds are kept in
+pub fn stripped_path(symdb: &Path) -> PathBuf {
+	let mut path = symdb.as_os_str().to_owned();
+	path.push(".stripped");
+	PathBuf::from(path)
+}
+
+fn read_stripped(path: &Path) -> BTreeMap<String, Stripped> {
+	match std::fs::read(path) {
+		Ok(x) => serde_json::from_slice(&x).unwrap_or_else(|e| panic!("Unable to read {}: {}", path.display(), e)),
+		Err(_) => BTreeMap::new()
+	}
+}
+
+/// Removes every matching bind, stashing it in the side file unless this is a dry run
+pub fn strip(binds: &mut BindDB, filter: &StripFilter, side: &Path, dry_run: bool) -> usize {
+	let base = binds.base.unwrap_or(0);
+
+	let mut matching: Vec<_> = binds.binds.iter()
+		.filter(|(x, y)| filter.matches(x, y, binds.sources.get(*x).map(|x| x.as_str())))
+		.map(|(x, y)| (x.clone(), y.clone()))
+		.collect();
+	matching.sort_by(|x, y| x.0.cmp(&y.0));
+
+	if dry_run {
+		for (symbol, bind) in &matching {
+			let source = binds.sources.get(symbol).map(|x| x.as_str());
+			println!("{} {} ({}, {:.2})",
+				demangle(symbol).yellow(),
+				bind.describe(base),
+				source.unwrap_or("unknown"),
+				confidence(bind, source)
+			);
+		}
+		return matching.len();
+	}
+
+	let mut stripped = read_stripped(side);
+	for (symbol, bind) in &matching {
+		stripped.insert(symbol.clone(), Stripped {
+			bind: bind.clone(),
+			source: binds.sources.get(symbol).cloned()
+		});
+		binds.remove(symbol, "strip");
+	}
+
+	if !matching.is_empty() {
+		write_atomic(side, serde_json::to_string_pretty(&stripped).unwrap().as_bytes());
+	}
+
+	matching.len()
+}
+
+/// Puts stripped binds back, leaving any whose symbol has been bound again in the side file
+pub fn restore(binds: &mut BindDB, side: &Path) -> usize {
+	let mut stripped = read_stripped(side);
+	let mut restored = 0;
+
+	stripped.retain(|symbol, entry| {
+		if binds.binds.contains_key(symbol) {
+			return true;
+		}
+
+		binds.set(symbol, entry.bind.clone(), "restore");
+		// Keep the provenance from before the strip
+		match &entry.source {
+			Some(x) => binds.sources.insert(symbol.clone(), x.clone()),
+			None => binds.sources.remove(symbol)
+		};
+		restored += 1;
+		false
+	});
+
+	if stripped.is_empty() {
+		let _ = std::fs::remove_file(side);
+	} else {
+		println!("{} stripped symbols were bound again and kept in {}", stripped.len().to_string().yellow(), side.display());
+		write_atomic(side, serde_json::to_string_pretty(&stripped).unwrap().as_bytes());
+	}
+
+	restored
+}
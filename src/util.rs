@@ -1,24 +1,59 @@
 use std::io::Write;
 use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 
 use crossterm::event;
 use crossterm::terminal::{enable_raw_mode, disable_raw_mode};
 use colored::Colorize;
+use serde::{Serialize, Deserialize};
+
+use crate::db::{ExecPair, BindDB};
 
 pub fn hex_to_u64(hex: &str) -> Option<u64> {
 	u64::from_str_radix(hex.strip_prefix("0x").unwrap_or("j"), 16).ok().warn_if(format!("Unable to decode hex: {}", hex))
 }
 
+// Print addresses relative to their image base instead of absolute
+static SHOW_RVA: AtomicBool = AtomicBool::new(false);
+
+pub fn show_rva(enabled: bool) {
+	SHOW_RVA.store(enabled, Ordering::Relaxed);
+}
+
+pub fn showing_rva() -> bool {
+	SHOW_RVA.load(Ordering::Relaxed)
+}
+
+/// Parses an address typed by the user, which is an RVA when `--rva` is given
+pub fn parse_addr(hex: &str, base: u64) -> Option<u64> {
+	let addr = hex_to_u64(hex)?;
+	if showing_rva() {
+		Some(addr.wrapping_add(base))
+	} else {
+		Some(addr)
+	}
+}
+
 pub trait AsHex {
 	fn as_hex(&self) -> String;
+	fn as_addr(&self, base: u64) -> String;
 }
 
 impl AsHex for u64 {
 	fn as_hex(&self) -> String {
 		format!("{:#x}", self)
 	}
+
+	fn as_addr(&self, base: u64) -> String {
+		if showing_rva() {
+			format!("+{:#x}", self.wrapping_sub(base))
+		} else {
+			self.as_hex()
+		}
+	}
 }
 
+#[allow(clippy::wrong_self_convention)]
 pub trait AsSome {
 	fn as_some(self) -> Option<Self> where Self: Sized;
 }
@@ -41,7 +76,7 @@ impl<T, E: std::fmt::Display> Warn<T> for Result<T, E> {
 }
 impl<T> Warn<T> for Option<T> {
 	fn warn_if(self, msg: impl Display) -> Self {
-		if let None = &self {
+		if self.is_none() {
 			println!("{}", msg);
 		}
 		self
@@ -83,11 +118,58 @@ fn confirm(msg: &str) -> Option<bool> {
 	}
 }
 
-pub fn conflict_confirm(sym: &str, addr: u64) -> Option<bool> {
-	let sym_demangle = cpp_demangle::Symbol::new(sym)
+pub fn demangle(sym: &str) -> String {
+	cpp_demangle::Symbol::new(sym)
 		.map(|x| x.to_string())
-		.unwrap_or(sym.to_string());
+		.unwrap_or(sym.to_string())
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+	/// Prompt for every conflict
+	#[default]
+	Ask,
+	/// Leave conflicts unresolved, as if answered with ignore
+	Skip,
+	/// Answer no to every conflict
+	Reject
+}
+
+static CONFLICT_POLICY: AtomicU8 = AtomicU8::new(ConflictPolicy::Ask as u8);
+
+pub fn set_conflict_policy(policy: ConflictPolicy) {
+	CONFLICT_POLICY.store(policy as u8, Ordering::Relaxed);
+}
+
+// The answer the conflict policy gives without asking, if any
+fn policy_answer() -> Option<Option<bool>> {
+	match CONFLICT_POLICY.load(Ordering::Relaxed) {
+		x if x == ConflictPolicy::Skip as u8 => Some(None),
+		x if x == ConflictPolicy::Reject as u8 => Some(Some(false)),
+		_ => None
+	}
+}
 
-	confirm(&format!("Is {} located at {}", sym_demangle.yellow(), addr.as_hex().blue()))
+pub fn conflict_confirm(pair: &ExecPair, binds: &mut BindDB, sym: &str, addr: u64) -> Option<bool> {
+	if let Some(answer) = policy_answer() {
+		return answer;
+	}
+	// Ctrl-C exits from the prompt, so earlier answers have to be on disk by now
+	binds.flush_journal();
+
+	crate::diff::print_fn_diff(pair, binds, sym, addr);
+
+	confirm(&format!("Is {} located at {}", demangle(sym).yellow(), addr.as_addr(pair.output.meta.base).blue()))
 }
 
+pub fn inline_confirm(binds: &mut BindDB, sym: &str, evidence: &[String]) -> Option<bool> {
+	if let Some(answer) = policy_answer() {
+		return answer;
+	}
+	binds.flush_journal();
+
+	evidence.iter().for_each(|x| println!("  {}", x));
+
+	confirm(&format!("Was {} inlined", demangle(sym).yellow()))
+}
@@ -0,0 +1,192 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::*;
+use symbo::db::*;
+use symbo::journal;
+use symbo::merge;
+use symbo::check;
+use symbo::find::find_symbols;
+
+fn new(x: &[(&str, u64)]) -> HashMap<String, u64> {
+	x.iter().map(|(x, y)| (x.to_string(), *y)).collect()
+}
+
+#[test]
+fn process_adds_new_symbols_as_unverified() {
+	let pair = pair();
+	let (mut binds, _dir) = binds(&pair);
+
+	binds.process(&pair, "string_xref", new(&[("main", MAIN)]));
+
+	assert_eq!(binds.binds["main"], Bind::Unverified(MAIN));
+	assert_eq!(binds.sources["main"], "string_xref");
+}
+
+#[test]
+fn process_rejects_both_sides_of_a_conflict() {
+	let pair = pair();
+	let (mut binds, _dir) = binds(&pair);
+	binds.set("compute", Bind::Unverified(COMPUTE), "test");
+
+	binds.process(&pair, "call_block", new(&[("compute", OPEN_FILE)]));
+
+	assert_eq!(binds.binds["compute"], Bind::Not(vec![COMPUTE, OPEN_FILE]));
+}
+
+#[test]
+fn process_grows_not_lists() {
+	let pair = pair();
+	let (mut binds, _dir) = binds(&pair);
+	binds.set("compute", Bind::Not(vec![MAIN]), "test");
+
+	binds.process(&pair, "call_block", new(&[("compute", OPEN_FILE)]));
+	binds.process(&pair, "call_block", new(&[("compute", OPEN_FILE)]));
+
+	assert_eq!(binds.binds["compute"], Bind::Not(vec![MAIN, OPEN_FILE]));
+}
+
+#[test]
+fn process_leaves_verified_and_inline_alone() {
+	let pair = pair();
+	let (mut binds, _dir) = binds(&pair);
+	binds.set("main", Bind::Verified(MAIN), "test");
+	binds.set("compute", Bind::Inline, "test");
+
+	binds.process(&pair, "call_block", new(&[("main", OPEN_FILE), ("compute", COMPUTE)]));
+
+	assert_eq!(binds.binds["main"], Bind::Verified(MAIN));
+	assert_eq!(binds.binds["compute"], Bind::Inline);
+}
+
+#[test]
+fn process_rejects_addresses_verified_for_another_symbol() {
+	let pair = pair();
+	let (mut binds, _dir) = binds(&pair);
+	binds.set("main", Bind::Verified(MAIN), "test");
+	binds.set("compute", Bind::Unverified(COMPUTE), "test");
+
+	binds.process(&pair, "call_block", new(&[("compute", MAIN)]));
+
+	assert_eq!(binds.binds["compute"], Bind::Not(vec![MAIN]));
+}
+
+#[test]
+fn find_symbols_narrows_down_by_callers() {
+	let pair = pair();
+	let (mut binds, _dir) = binds(&pair);
+	binds.set("main", Bind::Verified(MAIN), "test");
+	binds.set("parse_args", Bind::Verified(PARSE_ARGS), "test");
+
+	// Everything but main is called from main alone, and parse_args is taken, so three are asked about
+	find_symbols(&pair, &mut binds, "compute".to_string(), &pair.output.fns);
+
+	let Bind::Not(mut asked) = binds.binds["compute"].clone() else {
+		panic!("compute should have been rejected");
+	};
+	asked.sort();
+	assert_eq!(asked, vec![COMPUTE, OPEN_FILE, READ_CONFIG]);
+	assert_eq!(binds.sources["compute"], "find");
+}
+
+#[test]
+fn find_symbols_skips_verified_symbols() {
+	let pair = pair();
+	let (mut binds, _dir) = binds(&pair);
+	binds.set("compute", Bind::Verified(COMPUTE), "test");
+
+	find_symbols(&pair, &mut binds, "compute".to_string(), &pair.output.fns);
+
+	assert_eq!(binds.binds["compute"], Bind::Verified(COMPUTE));
+}
+
+#[test]
+fn undo_steps_back_one_run_at_a_time() {
+	let pair = pair();
+	let (mut binds, dir) = binds(&pair);
+	let path = dir.path().join("test.symdb");
+
+	binds.set("main", Bind::Unverified(MAIN), "string_xref");
+	binds.save();
+	std::thread::sleep(std::time::Duration::from_millis(5));
+
+	let mut binds = BindDB::load(&path);
+	binds.set("compute", Bind::Unverified(COMPUTE), "block_traverse");
+	binds.save();
+
+	// Each plain undo takes the newest run that hasn't been reverted yet
+	for symbol in ["compute", "main"] {
+		let mut binds = BindDB::load(&path);
+		let entries = journal::read(&path);
+		let run = journal::last_run(&binds, &entries);
+
+		let reverted: Vec<_> = entries.iter().filter(|x| Some(x.run) == run).collect();
+		assert_eq!(reverted.iter().map(|x| x.symbol.as_str()).collect::<Vec<_>>(), vec![symbol]);
+		assert_eq!(journal::undo(&mut binds, &reverted), 1);
+		binds.save();
+		std::thread::sleep(std::time::Duration::from_millis(5));
+	}
+
+	let binds = BindDB::load(&path);
+	assert!(binds.binds.is_empty());
+	assert_eq!(journal::last_run(&binds, &journal::read(&path)), None);
+}
+
+#[test]
+fn save_compacts_the_journal() {
+	let pair = pair();
+	let (mut binds, dir) = binds(&pair);
+	let path = dir.path().join("test.symdb");
+
+	binds.set("main", Bind::Unverified(COMPUTE), "string_xref");
+	binds.set("main", Bind::Not(vec![COMPUTE]), "find");
+	binds.set("main", Bind::Verified(MAIN), "find");
+	binds.set("compute", Bind::Unverified(COMPUTE), "block_traverse");
+	binds.remove("compute", "undo");
+	binds.checkpoint();
+	binds.save();
+
+	// The rejection survives for check, everything around it folds away
+	let entries = journal::read(&path);
+	assert_eq!(entries.len(), 2);
+	assert!(entries.iter().all(|x| x.symbol == "main"));
+	assert_eq!(entries[0].old, None);
+	assert_eq!(entries[0].new, Some(Bind::Not(vec![COMPUTE])));
+	assert_eq!(entries[1].old, Some(Bind::Not(vec![COMPUTE])));
+	assert_eq!(entries[1].new, Some(Bind::Verified(MAIN)));
+	assert!(check::rejections(&entries)["main"].contains(&COMPUTE));
+
+	let loaded = BindDB::load(&path);
+	assert_eq!(loaded.binds.get("main"), Some(&Bind::Verified(MAIN)));
+	assert_eq!(loaded.binds.get("compute"), None);
+	assert_eq!(loaded.seq, 2);
+}
+
+#[test]
+fn merge_reports_two_symbols_verified_at_one_address() {
+	let pair = pair();
+	let (mut binds, _dir) = binds(&pair);
+	let (mut other, _other_dir) = common::binds(&pair);
+	binds.set("compute", Bind::Verified(COMPUTE), "test");
+	other.set("parse_args", Bind::Verified(COMPUTE), "test");
+
+	let stats = merge::merge(&mut binds, &other);
+
+	assert!(matches!(&stats.conflicts[..], [merge::Conflict::Address(COMPUTE, x)] if x == &["compute", "parse_args"]));
+}
+
+#[test]
+fn apply_prefers_verified_then_name_at_a_shared_address() {
+	let pair = pair();
+	let (mut binds, _dir) = binds(&pair);
+	binds.set("b_verified", Bind::Verified(COMPUTE), "test");
+	binds.set("a_unverified", Bind::Unverified(COMPUTE), "test");
+	binds.set("d_second", Bind::Unverified(OPEN_FILE), "test");
+	binds.set("c_first", Bind::Unverified(OPEN_FILE), "test");
+
+	let ExecPair { mut output, .. } = pair;
+	assert_eq!(output.apply_names(&binds, false), 2);
+	assert_eq!(output.fns[&COMPUTE].name.as_deref(), Some("b_verified"));
+	assert_eq!(output.fns[&OPEN_FILE].name.as_deref(), Some("c_first"));
+}
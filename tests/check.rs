@@ -0,0 +1,31 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::*;
+use symbo::check::{check, IssueKind};
+use symbo::db::*;
+
+fn shared(binds: &BindDB) -> Vec<String> {
+	let mut out: Vec<_> = check(&pair(), binds, &HashMap::new()).into_iter()
+		.filter(|x| x.kind == IssueKind::SharedAddress)
+		.map(|x| x.symbol)
+		.collect();
+	out.sort();
+	out
+}
+
+#[test]
+fn ctor_and_dtor_variants_may_share_an_address() {
+	let pair = pair();
+	let (mut binds, _dir) = binds(&pair);
+
+	binds.set("_ZN3FooC1Ev", Bind::Verified(MAIN), "test");
+	binds.set("_ZN3FooC2Ev", Bind::Verified(MAIN), "test");
+	binds.set("_ZN3FooD1Ev", Bind::Verified(COMPUTE), "test");
+	binds.set("_ZN3FooD2Ev", Bind::Verified(COMPUTE), "test");
+	assert!(shared(&binds).is_empty());
+
+	binds.set("_ZN3Foo3barEv", Bind::Verified(MAIN), "test");
+	assert_eq!(shared(&binds), ["_ZN3Foo3barEv", "_ZN3FooC1Ev", "_ZN3FooC2Ev"]);
+}
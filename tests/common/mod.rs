@@ -0,0 +1,42 @@
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use std::sync::Once;
+
+use symbo::db::*;
+use symbo::util::{set_conflict_policy, ConflictPolicy};
+use tempfile::TempDir;
+
+// Both fixtures are the same five functions: main calls parse_args and compute, then open_file and read_config
+// The rizin sessions behind them are synthetic, written by tests/fixtures/synthetic.py rather than recorded
+pub const MAIN: u64 = 0x401000;
+pub const PARSE_ARGS: u64 = 0x401120;
+pub const COMPUTE: u64 = 0x401220;
+pub const OPEN_FILE: u64 = 0x401320;
+pub const READ_CONFIG: u64 = 0x401420;
+
+pub fn fixture(name: &str) -> PathBuf {
+	Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+pub fn load_exdb(name: &str) -> ExecDB {
+	let data = std::fs::read(fixture(&format!("{}.exdb.json", name))).unwrap();
+	serde_json::from_slice(&data).unwrap()
+}
+
+pub fn pair() -> ExecPair {
+	ExecPair {
+		input: load_exdb("old"),
+		output: load_exdb("new")
+	}
+}
+
+/// An empty symdb in a scratch directory, nobody is around to answer conflicts
+pub fn binds(pair: &ExecPair) -> (BindDB, TempDir) {
+	static POLICY: Once = Once::new();
+	POLICY.call_once(|| set_conflict_policy(ConflictPolicy::Reject));
+
+	let dir = tempfile::tempdir().unwrap();
+	let binds = BindDB::open(&dir.path().join("test.symdb"), pair);
+	(binds, dir)
+}
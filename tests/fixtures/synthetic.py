@@ -0,0 +1,107 @@
+# Writes old.rizin.json and new.rizin.json. They are synthetic, not recorded from a real rizin session:
+# the addresses are invented round numbers, the hashes are made up and some outputs (abi in particular)
+# only carry what generate reads. Rerun this after editing it, then bless the exdbs with
+#   SYMBO_BLESS=1 cargo test --test generate replay_matches_exdb_fixtures
+import json
+import os
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+BASE=0x400000
+PDQ="pDq `afi~size[1]` @@= `cat {}`"
+
+def key(cmd, offs=None):
+    if offs is None: return cmd
+    return cmd+" < "+" ".join(hex(x) for x in offs)
+
+def build(path, sha1, fns, named, strings, imports, globals_):
+    # fns: name -> (label, addr, blocks); blocks: list of (addr, size, jump, [(addr, inst)])
+    rec={}
+    rec[key("ij")]=json.dumps({"core":{"file":path,"size":16384},"bin":{"arch":"x86","bits":64,"baddr":BASE,"bintype":"elf","os":"linux"}})
+    rec[key("itj")]=json.dumps({"md5":"0"*32,"sha1":sha1})
+    fl=sorted(fns.values(), key=lambda f:f[1])
+    rec[key("aflq")]="\n".join("0x%08x %s"%(f[1],f[0]) for f in fl)
+    addrs=[f[1] for f in fl]
+    rec[key("afbj @@. {}",addrs)]="\n".join(json.dumps([{"addr":b[0],"size":b[1],"jump":b[2]} if b[2] else {"addr":b[0],"size":b[1]} for b in f[2]]) for f in fl)
+    rec[key("isq~Z")]="\n".join("0x%08x %d %s"%(fns[n][1],sum(b[1] for b in fns[n][2]),n) for n in named)
+    rec[key("iij")]=json.dumps([{"ordinal":i+1,"name":n,"type":"FUNC","bind":"GLOBAL","plt":p} for i,(n,p) in enumerate(imports)])
+    rec[key("avj")]="[]"
+    rec[key("avrj @@= `cat {}`",[])]=""
+    # call xrefs: from pDq calls to labels
+    label={f[0]:f[1] for f in fl}
+    calls={a:[] for a in addrs}
+    for f in fl:
+        for b in f[2]:
+            for a,i in b[3]:
+                if i.startswith("call ") and i[5:] in label:
+                    calls[label[i[5:]]].append({"from":a,"to":label[i[5:]],"type":"CALL","opcode":i,"fcn_addr":f[1],"fcn_name":f[0]})
+    rec[key("axtj @@. {}",addrs)]="\n".join(json.dumps(calls[a]) for a in addrs)
+    bks=sorted(b for f in fl for b in f[2])
+    keys=[b[0] for b in bks]
+    def last(b): return b[3][-1][1]
+    rec[key("abi @@. {}",keys)]="\n".join(
+        ("0x%08x %d 0x%08x %d 0x%08x"%(b[0],len(b[3]),b[3][-1][0],b[1],b[0]+b[1])) + (" 0x%08x"%b[2] if b[2] else "") for b in bks)
+    ends=[b[0]+b[1] for b in bks]
+    rec[key("pi -1 @@. {}",ends)]="\n".join(last(b) for b in bks)
+    rec[key(PDQ,addrs)]="\n".join("0x%08x %s"%(a,i) for f in fl for b in f[2] for a,i in b[3])
+    # strings: addr -> text, xrefs from lea str.x
+    rec[key("izq")]="\n".join("0x%08x %d %d %s"%(a,len(t)+1,len(t),t) for a,t in strings)
+    sref=[]
+    for a,t in strings:
+        nm="str."+t.replace("-","_")
+        sref.append([{"from":ia,"type":"DATA","opcode":ii,"fcn_addr":f[1],"fcn_name":f[0]} for f in fl for b in f[2] for ia,ii in b[3] if ii.endswith(" "+nm)])
+    rec[key("axtj @@. {}",[a for a,_ in strings])]="\n".join(json.dumps(x) for x in sref)
+    # axffj per function: data refs to strings and globals plus calls
+    gaddr={n:a for n,a in globals_}
+    saddr={"str."+t.replace("-","_"):a for a,t in strings}
+    out=[]
+    for f in fl:
+        refs=[]
+        for b in f[2]:
+            for a,i in b[3]:
+                op=i.split(" ",1)
+                arg=op[1] if len(op)>1 else ""
+                for n,ga in gaddr.items():
+                    if n in arg: refs.append({"type":"DATA","at":a,"ref":ga,"name":n})
+                for n,sa in saddr.items():
+                    if arg.endswith(" "+n): refs.append({"type":"DATA","at":a,"ref":sa,"name":n})
+                if op[0]=="call":
+                    t=label.get(arg) or dict(imports).get(arg.replace("sym.imp.",""))
+                    if t: refs.append({"type":"CALL","at":a,"ref":t,"name":arg})
+        out.append(json.dumps(refs))
+    rec[key("axffj @@. {}",addrs)]="\n".join(out)
+    return {"commands":dict(sorted(rec.items()))}
+
+old_fns={
+ "main":("main",0x401000,[
+   (0x401000,0x20,0x401040,[(0x401000,"push rbp"),(0x401001,"lea rdi, str.usage"),(0x401008,"call sym.parse_args"),(0x40100d,"call sym.compute"),(0x401012,"mov ecx, 0xcafe"),(0x401017,"test eax, eax"),(0x401019,"je 0x401040")]),
+   (0x401020,0x20,0x401040,[(0x401020,"lea rdi, str.failed"),(0x401027,"call sym.open_file"),(0x40102c,"call sym.read_config"),(0x401031,"jmp 0x401040")]),
+   (0x401040,0x8,0,[(0x401040,"xor eax, eax"),(0x401042,"pop rbp"),(0x401043,"ret")])]),
+ "parse_args":("sym.parse_args",0x401100,[
+   (0x401100,0x10,0,[(0x401100,"lea rsi, str.__verbose"),(0x401107,"mov eax, 1"),(0x40110c,"ret")])]),
+ "compute":("sym.compute",0x401200,[
+   (0x401200,0x10,0,[(0x401200,"mov eax, 0x1337"),(0x401205,"imul eax, edi"),(0x401208,"ret")])]),
+ "open_file":("sym.open_file",0x401300,[
+   (0x401300,0x10,0,[(0x401300,"sub rsp, 8"),(0x401304,"call sym.imp.fopen"),(0x401309,"add rsp, 8"),(0x40130d,"ret")])]),
+ "read_config":("sym.read_config",0x401400,[
+   (0x401400,0x10,0,[(0x401400,"mov rax, qword [obj.g_config]"),(0x401407,"mov eax, dword [rax + 8]"),(0x40140a,"ret")])]),
+}
+new_fns={
+ "main":("main",0x401000,[
+   (0x401000,0x24,0x40102c,[(0x401000,"push rbp"),(0x401001,"lea rdi, str.usage"),(0x401008,"call fcn.00401120"),(0x40100d,"call fcn.00401220"),(0x401012,"mov ecx, 0xcafe"),(0x401017,"mov edx, 2"),(0x40101c,"test eax, eax"),(0x40101e,"jne 0x40102c")]),
+   (0x401024,0x8,0,[(0x401024,"xor eax, eax"),(0x401026,"pop rbp"),(0x401027,"ret")]),
+   (0x40102c,0x20,0x401024,[(0x40102c,"lea rdi, str.failed"),(0x401033,"call fcn.00401320"),(0x401038,"call fcn.00401420"),(0x40103d,"jmp 0x401024")])]),
+ "parse_args":("fcn.00401120",0x401120,[
+   (0x401120,0x10,0,[(0x401120,"lea rsi, str.__verbose"),(0x401127,"mov eax, 1"),(0x40112c,"ret")])]),
+ "compute":("fcn.00401220",0x401220,[
+   (0x401220,0x10,0,[(0x401220,"mov eax, 0x1337"),(0x401225,"imul eax, edi"),(0x401228,"ret")])]),
+ "open_file":("fcn.00401320",0x401320,[
+   (0x401320,0x10,0,[(0x401320,"sub rsp, 8"),(0x401324,"call sym.imp.fopen"),(0x401329,"add rsp, 8"),(0x40132d,"ret")])]),
+ "read_config":("fcn.00401420",0x401420,[
+   (0x401420,0x10,0,[(0x401420,"mov rax, qword [obj.g_config]"),(0x401427,"mov eax, dword [rax + 8]"),(0x40142a,"ret")])]),
+}
+strings_old=[(0x402000,"usage"),(0x402010,"failed"),(0x402020,"--verbose")]
+strings_new=[(0x402000,"usage"),(0x402008,"failed"),(0x402010,"--verbose")]
+old=build("old","8c1e0f0e3d2f8f6e2a3c4b5a69788796a5b4c3d2",old_fns,["main","parse_args","compute","open_file","read_config"],strings_old,[("fopen",0x400500)],[("obj.g_config",0x404010)])
+new=build("new","1f2e3d4c5b6a79881726354453627180f9e8d7c6",new_fns,["main"],strings_new,[("fopen",0x400520)],[("obj.g_config",0x404020)])
+json.dump(old,open(os.path.join(HERE, "old.rizin.json"),"w"),indent=2)
+json.dump(new,open(os.path.join(HERE, "new.rizin.json"),"w"),indent=2)
@@ -0,0 +1,85 @@
+mod common;
+
+use common::*;
+use symbo::arch::{Arch, get_branch_type};
+use symbo::db::*;
+use symbo::generate::{generate_from, nearest_block};
+use symbo::pipes::{Recording, Replay, Rizin};
+
+fn replay(name: &str) -> ExecDB {
+	let mut pipe = Replay(Recording::load(&fixture(&format!("{}.rizin.json", name))));
+	generate_from(&mut pipe).unwrap()
+}
+
+/// The checked-in exdbs are what generate makes of the synthetic sessions, run with SYMBO_BLESS=1 to rewrite them
+#[test]
+fn replay_matches_exdb_fixtures() {
+	for name in ["old", "new"] {
+		let exec = serde_json::to_value(replay(name)).unwrap();
+		let path = fixture(&format!("{}.exdb.json", name));
+
+		if std::env::var_os("SYMBO_BLESS").is_some() {
+			std::fs::write(&path, serde_json::to_string_pretty(&exec).unwrap()).unwrap();
+		}
+
+		let expected: serde_json::Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+		assert_eq!(exec, expected, "{} no longer generates the same exdb", name);
+	}
+}
+
+#[test]
+fn replay_reads_the_recording() {
+	let exec = replay("old");
+
+	assert_eq!(exec.meta.arch, "x86");
+	assert_eq!(exec.meta.bits, 64);
+	assert_eq!(exec.meta.base, 0x400000);
+	assert_eq!(exec.fns.len(), 5);
+
+	let main = &exec.fns[&0x401000];
+	assert_eq!(main.name.as_deref(), Some("main"));
+	assert_eq!(main.blocks.len(), 3);
+	assert_eq!(main.blocks[0].calls, vec![Dest::Known(0x401100), Dest::Known(0x401200)]);
+	assert_eq!(main.blocks[0].strings, vec!["usage".to_string()]);
+	assert_eq!(main.blocks[0].constants, vec![0xcafe]);
+	assert_eq!(main.blocks[0].branch, Branch::Equality(Dest::Known(0x401040), Dest::Known(0x401020)));
+	assert_eq!(main.blocks[2].branch, Branch::Return);
+
+	assert_eq!(exec.fns[&0x401300].blocks[0].calls, vec![Dest::Import("fopen".to_string())]);
+	assert_eq!(exec.imports["fopen"].xrefs.len(), 1);
+	assert_eq!(exec.globals[&0x404010].name.as_deref(), Some("g_config"));
+	assert_eq!(exec.fns[&0x401200].xrefs.iter().map(|x| x.function_addr).collect::<Vec<_>>(), vec![0x401000]);
+}
+
+#[test]
+fn replay_without_a_recording_fails() {
+	let mut pipe = Replay(Recording::default());
+	assert!(pipe.cmd("ij").is_err());
+	assert!(generate_from(&mut pipe).is_err());
+}
+
+#[test]
+fn nearest_block_finds_the_containing_block() {
+	let blocks = [0x1000, 0x1020, 0x1040];
+
+	assert_eq!(nearest_block(0x1000, &blocks), Some(0x1000));
+	assert_eq!(nearest_block(0x1020, &blocks), Some(0x1020));
+	assert_eq!(nearest_block(0x1033, &blocks), Some(0x1020));
+	assert_eq!(nearest_block(0x1fff, &blocks), Some(0x1040));
+	assert_eq!(nearest_block(0x0fff, &blocks), None);
+	assert_eq!(nearest_block(0x1000, &[]), None);
+}
+
+#[test]
+fn branch_types_line_up_across_versions() {
+	// The old build tests for equal and the new one for not equal, with the blocks swapped around
+	let old = get_branch_type(Arch::X86, "je 0x401040", 0x401040, 0x401020);
+	let new = get_branch_type(Arch::X86, "jne 0x40102c", 0x40102c, 0x401024);
+
+	assert_eq!(old, Branch::Equality(Dest::Known(0x401040), Dest::Known(0x401020)));
+	assert_eq!(new, Branch::Equality(Dest::Known(0x401024), Dest::Known(0x40102c)));
+
+	assert_eq!(get_branch_type(Arch::X86, "ret", 0, 0x401044), Branch::Return);
+	assert_eq!(get_branch_type(Arch::X86, "jmp rax", 0, 0x401044), Branch::Neutral(Dest::Unknown));
+	assert_eq!(get_branch_type(Arch::Arm64, "b.gt 0x2000", 0x2000, 0x1004), Branch::Inequality(Dest::Known(0x2000), Dest::Known(0x1004)));
+}
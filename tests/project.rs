@@ -0,0 +1,44 @@
+use std::process::Command;
+
+use symbo::db::*;
+
+fn symbo(dir: &std::path::Path, args: &[&str]) -> std::process::Output {
+	Command::new(env!("CARGO_BIN_EXE_symbo"))
+		.args(args)
+		.current_dir(dir)
+		.output()
+		.unwrap()
+}
+
+#[test]
+fn symdb_commands_default_to_the_project_symdb() {
+	let dir = tempfile::tempdir().unwrap();
+	std::fs::write(dir.path().join("symbo.toml"), "symdb = \"project.symdb\"\n").unwrap();
+	std::fs::write(dir.path().join("project.symdb"), r#"{"version": 3, "binds": {"main": {"Verified": 4198400}}}"#).unwrap();
+	std::fs::write(dir.path().join("other.symdb"), r#"{"version": 3, "binds": {"compute": {"Verified": 4198944}}}"#).unwrap();
+
+	let out = symbo(dir.path(), &["diff-db", "other.symdb"]);
+	assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+	let text = String::from_utf8_lossy(&out.stdout);
+	assert!(text.contains("Added") && text.contains("Removed"), "{}", text);
+
+	let out = symbo(dir.path(), &["merge", "other.symdb"]);
+	assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+	let binds = BindDB::load(&dir.path().join("project.symdb"));
+	assert_eq!(binds.binds["main"], Bind::Verified(0x401000));
+	assert_eq!(binds.binds["compute"], Bind::Verified(0x401220));
+}
+
+#[test]
+fn exdb_paths_keep_their_original_position() {
+	let dir = tempfile::tempdir().unwrap();
+
+	// Only one exdb is ambiguous, it's refused before anything is loaded
+	let out = symbo(dir.path(), &["range", "old.exdb", "0x1000", "0x2000", "-c", "Foo"]);
+	assert!(!out.status.success());
+	assert!(String::from_utf8_lossy(&out.stderr).contains("both FROM and TO"));
+
+	// Both exdbs are taken from the front, as before projects
+	let out = symbo(dir.path(), &["diff", "old.exdb", "new.exdb", "main"]);
+	assert!(String::from_utf8_lossy(&out.stderr).contains("old.exdb"));
+}
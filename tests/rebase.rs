@@ -0,0 +1,58 @@
+use std::process::Command;
+
+use symbo::db::*;
+
+// Written before symdbs recorded their base, so the addresses are absolute
+const BASELESS: &str = r#"{"binds": {"main": {"Verified": 4198400}, "compute": {"Not": [4198944]}}}"#;
+
+fn symbo(dir: &std::path::Path, args: &[&str]) -> std::process::Output {
+	Command::new(env!("CARGO_BIN_EXE_symbo"))
+		.args(args)
+		.current_dir(dir)
+		.output()
+		.unwrap()
+}
+
+#[test]
+fn rebasing_a_baseless_symdb_needs_its_old_base() {
+	let dir = tempfile::tempdir().unwrap();
+	let path = dir.path().join("old.symdb");
+	std::fs::write(&path, BASELESS).unwrap();
+
+	let out = symbo(dir.path(), &["rebase", "old.symdb", "0x10000000"]);
+	assert!(!out.status.success());
+	assert!(String::from_utf8_lossy(&out.stderr).contains("--from-base"));
+	assert_eq!(std::fs::read_to_string(&path).unwrap(), BASELESS);
+
+	let out = symbo(dir.path(), &["rebase", "old.symdb", "0x10000000", "--from-base", "0x400000"]);
+	assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+
+	let binds = BindDB::load(&path);
+	assert_eq!(binds.base, Some(0x10000000));
+	assert_eq!(binds.binds["main"], Bind::Verified(0x10001000));
+	assert_eq!(binds.binds["compute"], Bind::Not(vec![0x10001220]));
+}
+
+#[test]
+fn rebasing_refuses_a_conflicting_old_base() {
+	let dir = tempfile::tempdir().unwrap();
+	let path = dir.path().join("new.symdb");
+	std::fs::write(&path, r#"{"version": 3, "base": 4194304, "binds": {"main": {"Verified": 4198400}}}"#).unwrap();
+
+	let out = symbo(dir.path(), &["rebase", "new.symdb", "0x10000000", "--from-base", "0x500000"]);
+	assert!(!out.status.success());
+
+	let out = symbo(dir.path(), &["rebase", "new.symdb", "0x10000000"]);
+	assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+	assert_eq!(BindDB::load(&path).binds["main"], Bind::Verified(0x10001000));
+}
+
+#[test]
+fn diffing_against_a_baseless_symdb_compares_addresses_as_they_are() {
+	let dir = tempfile::tempdir().unwrap();
+	let (old, new) = (dir.path().join("old.symdb"), dir.path().join("new.symdb"));
+	std::fs::write(&old, BASELESS).unwrap();
+	std::fs::write(&new, r#"{"version": 3, "base": 4194304, "binds": {"main": {"Verified": 4198400}, "compute": {"Not": [4198944]}}}"#).unwrap();
+
+	assert!(symbo::dbdiff::diff(&BindDB::load(&old), &BindDB::load(&new)).is_empty());
+}
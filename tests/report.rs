@@ -0,0 +1,35 @@
+mod common;
+
+use common::*;
+use symbo::db::*;
+use symbo::report::{build, scope_of};
+
+#[test]
+fn scope_keeps_template_arguments_with_their_class() {
+	assert_eq!(scope_of("_ZN7cocos2d6CCNode4initEv"), ["cocos2d", "CCNode"]);
+	assert_eq!(scope_of("_ZN2ns3FooIN1a1bEE3barEv"), ["ns", "Foo<a::b>"]);
+	assert_eq!(scope_of("_ZNK2ns3FooIiNS_3BarEEC2Ev"), ["ns", "Foo<int, ns::Bar>"]);
+	assert_eq!(scope_of("_ZN2ns3fooIiEEvT_"), ["ns"]);
+	assert_eq!(scope_of("_ZN3MapI3KeyLi4EE3getEv"), ["Map<Key, 4>"]);
+	assert!(scope_of("main").is_empty());
+}
+
+#[test]
+fn scope_keeps_the_class_of_operators() {
+	assert_eq!(scope_of("_ZN3FooplERKS_"), ["Foo"]);
+	assert_eq!(scope_of("_ZN3FooaSERKS_"), ["Foo"]);
+	assert_eq!(scope_of("_ZNK2ns3FooeqERKS0_"), ["ns", "Foo"]);
+	assert_eq!(scope_of("_ZNK3FoocviEv"), ["Foo"]);
+	assert_eq!(scope_of("_ZN3Foo3barB5cxx11Ev"), ["Foo"]);
+}
+
+#[test]
+fn inlined_functions_are_not_listed_as_unmatched() {
+	let pair = pair();
+	let (mut binds, _dir) = binds(&pair);
+	binds.set("compute", Bind::Inline, "test");
+
+	let unmatched: Vec<_> = build(&pair, &binds, 20).unmatched.into_iter().map(|x| x.symbol).collect();
+	assert!(unmatched.contains(&"parse_args".to_string()));
+	assert!(!unmatched.contains(&"compute".to_string()));
+}
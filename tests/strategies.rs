@@ -0,0 +1,149 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::*;
+use symbo::analysis::*;
+use symbo::db::*;
+
+fn found(x: &[(&str, u64)]) -> HashMap<String, u64> {
+	x.iter().map(|(x, y)| (x.to_string(), *y)).collect()
+}
+
+fn verified(binds: &mut BindDB, x: &[(&str, u64)]) {
+	for (symbol, addr) in x {
+		binds.set(symbol, Bind::Verified(*addr), "test");
+	}
+}
+
+#[test]
+fn string_xref_binds_functions_using_unique_strings() {
+	let pair = pair();
+	let (binds, _dir) = binds(&pair);
+
+	assert_eq!(string_xref_strat(&pair, &binds), found(&[("main", MAIN), ("parse_args", PARSE_ARGS)]));
+}
+
+#[test]
+fn import_xref_binds_the_only_caller_of_an_import() {
+	let pair = pair();
+	let (binds, _dir) = binds(&pair);
+
+	assert_eq!(import_xref_strat(&pair, &binds), found(&[("open_file", OPEN_FILE)]));
+}
+
+#[test]
+fn global_xref_binds_the_only_user_of_a_global() {
+	let pair = pair();
+	let (binds, _dir) = binds(&pair);
+
+	assert_eq!(match_globals(&pair).len(), 1);
+	assert_eq!(global_xref_strat(&pair, &binds), found(&[("read_config", READ_CONFIG)]));
+}
+
+#[test]
+fn globals_match_through_the_vtable_start() {
+	let mut pair = pair();
+	pair.output.meta.bits = 32;
+
+	// Unnamed vtable symbols, rizin's address is the first method slot past offset to top and typeinfo
+	for (exec, point, ptr) in [(&mut pair.input, 0x403010, 8), (&mut pair.output, 0x403108, 4)] {
+		exec.vtables.insert("Foo".to_string(), Vtable { name: "Foo".to_string(), address: point, function_addrs: vec![] });
+		exec.globals.insert(point - 2 * ptr, Global { name: None, address: point - 2 * ptr, xrefs: vec![] });
+	}
+
+	let matched: Vec<_> = match_globals(&pair).into_iter().map(|(x, y)| (x.address, y.address)).collect();
+	assert!(matched.contains(&(0x403000, 0x403100)), "{:x?}", matched);
+}
+
+#[test]
+fn blocks_match_on_constants_in_any_order() {
+	let mut pair = pair();
+	// The input is unstripped, so find its functions by name
+	let named = |x: &str| *pair.input.fns.iter().find(|y| y.1.name.as_deref() == Some(x)).unwrap().0;
+	let (compute, read_config) = (named("compute"), named("read_config"));
+
+	pair.input.fns.get_mut(&compute).unwrap().blocks[0].constants.push(0x4000);
+	pair.output.fns.get_mut(&COMPUTE).unwrap().blocks[0].constants.insert(0, 0x4000);
+	let (binds, _dir) = binds(&pair);
+
+	let at = |x: &ExecDB, addr| x.fns[&addr].address;
+	let input = vec![at(&pair.input, compute), at(&pair.input, read_config)];
+	let output = vec![at(&pair.output, READ_CONFIG), at(&pair.output, COMPUTE)];
+
+	assert_eq!(xref_binds(&binds, &pair, vec![(&input, &output)]), found(&[("compute", COMPUTE)]));
+}
+
+#[test]
+fn block_traverse_follows_an_inverted_branch() {
+	let pair = pair();
+	let (mut binds, _dir) = binds(&pair);
+	verified(&mut binds, &[("main", MAIN)]);
+
+	assert_eq!(block_traverse_strat(&pair, &binds), found(&[
+		("parse_args", PARSE_ARGS),
+		("compute", COMPUTE),
+		("open_file", OPEN_FILE),
+		("read_config", READ_CONFIG)
+	]));
+}
+
+#[test]
+fn call_xref_binds_the_only_caller() {
+	let pair = pair();
+	let (mut binds, _dir) = binds(&pair);
+	verified(&mut binds, &[("parse_args", PARSE_ARGS)]);
+
+	assert_eq!(call_xref_strat(&pair, &binds), found(&[("main", MAIN)]));
+}
+
+#[test]
+fn call_block_binds_calls_next_to_a_bound_one() {
+	let pair = pair();
+	let (mut binds, _dir) = binds(&pair);
+	verified(&mut binds, &[("main", MAIN), ("open_file", OPEN_FILE)]);
+
+	assert_eq!(call_block_strat(&pair, &binds), found(&[("open_file", OPEN_FILE), ("read_config", READ_CONFIG)]));
+}
+
+#[test]
+fn strategies_in_order_bind_everything() {
+	let pair = pair();
+	let (mut binds, _dir) = binds(&pair);
+	let names: Vec<_> = STRATEGIES.iter().map(|x| x.0.to_string()).collect();
+
+	run_strategies(&pair, &mut binds, &names);
+
+	for (symbol, addr) in [("main", MAIN), ("parse_args", PARSE_ARGS), ("compute", COMPUTE), ("open_file", OPEN_FILE), ("read_config", READ_CONFIG)] {
+		assert_eq!(binds.binds.get(symbol), Some(&Bind::Unverified(addr)), "{}", symbol);
+	}
+	assert_eq!(binds.sources["main"], "string_xref");
+	assert_eq!(binds.sources["compute"], "block_traverse");
+}
+
+#[test]
+fn inline_needs_the_missing_call_to_be_the_candidates() {
+	let detected = |calls: Vec<Dest>| {
+		let mut pair = pair();
+		// Its string turning up in main is what backs the missing call up
+		let read_config = pair.input.fns.values_mut().find(|x| x.name.as_deref() == Some("read_config")).unwrap();
+		read_config.blocks[0].strings.push("config.ini".to_string());
+
+		let main = pair.output.fns.get_mut(&MAIN).unwrap();
+		let site = main.blocks.iter_mut().find(|x| x.calls.contains(&Dest::Known(READ_CONFIG))).unwrap();
+		site.calls = calls;
+		site.strings.push("config.ini".to_string());
+
+		let (mut binds, _dir) = binds(&pair);
+		verified(&mut binds, &[("main", MAIN), ("open_file", OPEN_FILE)]);
+
+		let mut out: Vec<_> = symbo::inline::detect(&pair, &binds).into_iter().map(|x| x.symbol).collect();
+		out.sort();
+		out
+	};
+
+	// read_config's call is the one left without a counterpart
+	assert_eq!(detected(vec![Dest::Known(OPEN_FILE)]), ["read_config"]);
+	// open_file's call went missing instead, read_config's lines up with the remaining one
+	assert!(detected(vec![Dest::Known(PARSE_ARGS)]).is_empty());
+}